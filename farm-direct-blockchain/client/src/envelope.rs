@@ -0,0 +1,192 @@
+// farm-direct-blockchain/client/src/envelope.rs
+//
+// Seals and opens the envelopes described in the program's `envelope` module.
+use std::fmt;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use farm_direct_blockchain::envelope::{
+    DATA_KEY_LEN, ENVELOPE_VERSION, NONCE_LEN, PROFILE_DATA_HEADER_LEN, PROFILE_DATA_PREFIX,
+    TAG_LEN, WRAPPED_KEY_HEADER_LEN, WRAPPED_KEY_LEN, WRAP_INFO, X25519_KEY_LEN,
+};
+use hkdf::Hkdf;
+use rand_core::{OsRng, RngCore};
+use sha2::Sha256;
+use x25519_dalek::SharedSecret;
+pub use x25519_dalek::{PublicKey, StaticSecret};
+
+pub type DataKey = [u8; DATA_KEY_LEN];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvelopeError {
+    Malformed,
+    UnsupportedVersion(u8),
+    // Wrong key, or the envelope was tampered with
+    Decryption,
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => write!(f, "malformed envelope"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported envelope version {version}")
+            }
+            Self::Decryption => write!(f, "envelope could not be decrypted"),
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+pub fn generate_data_key() -> DataKey {
+    let mut key = [0; DATA_KEY_LEN];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+// A buyer's long-lived encryption key; the public half goes into `grant_access`
+pub fn generate_encryption_key() -> (StaticSecret, PublicKey) {
+    let secret = StaticSecret::random_from_rng(OsRng);
+    let public = PublicKey::from(&secret);
+    (secret, public)
+}
+
+/// Encrypts profile data into the `enc:v1:` form stored in `encrypted_data`.
+pub fn seal_profile_data(data_key: &DataKey, plaintext: &[u8]) -> String {
+    let nonce = random_nonce();
+    let header = [ENVELOPE_VERSION];
+    let ciphertext = XChaCha20Poly1305::new(data_key.into())
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: &header,
+            },
+        )
+        .expect("encrypting into a Vec cannot fail");
+
+    let mut sealed = Vec::with_capacity(PROFILE_DATA_HEADER_LEN + ciphertext.len());
+    sealed.extend_from_slice(&header);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+
+    format!("{PROFILE_DATA_PREFIX}{}", STANDARD.encode(sealed))
+}
+
+pub fn open_profile_data(
+    data_key: &DataKey,
+    encrypted_data: &str,
+) -> Result<Vec<u8>, EnvelopeError> {
+    let encoded = encrypted_data
+        .strip_prefix(PROFILE_DATA_PREFIX)
+        .ok_or(EnvelopeError::Malformed)?;
+    let sealed = STANDARD
+        .decode(encoded)
+        .map_err(|_| EnvelopeError::Malformed)?;
+    if sealed.len() < PROFILE_DATA_HEADER_LEN + TAG_LEN {
+        return Err(EnvelopeError::Malformed);
+    }
+    check_version(sealed[0])?;
+
+    let (header, rest) = sealed.split_at(1);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(data_key.into())
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| EnvelopeError::Decryption)
+}
+
+/// Wraps `data_key` to a buyer's X25519 key for `grant_access`.
+pub fn wrap_data_key(data_key: &DataKey, recipient: &PublicKey) -> Vec<u8> {
+    let ephemeral = StaticSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(recipient);
+    let cipher = wrapping_cipher(&shared, &ephemeral_public, recipient);
+
+    let mut wrapped = Vec::with_capacity(WRAPPED_KEY_LEN);
+    wrapped.push(ENVELOPE_VERSION);
+    wrapped.extend_from_slice(ephemeral_public.as_bytes());
+    let nonce = random_nonce();
+    let sealed_key = cipher
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: data_key,
+                aad: &wrapped,
+            },
+        )
+        .expect("encrypting into a Vec cannot fail");
+    wrapped.extend_from_slice(&nonce);
+    wrapped.extend_from_slice(&sealed_key);
+
+    wrapped
+}
+
+pub fn unwrap_data_key(
+    wrapped_key: &[u8],
+    recipient: &StaticSecret,
+) -> Result<DataKey, EnvelopeError> {
+    if wrapped_key.len() != WRAPPED_KEY_LEN {
+        return Err(EnvelopeError::Malformed);
+    }
+    check_version(wrapped_key[0])?;
+
+    let aad = &wrapped_key[..1 + X25519_KEY_LEN];
+    let mut ephemeral_bytes = [0; X25519_KEY_LEN];
+    ephemeral_bytes.copy_from_slice(&aad[1..]);
+    let ephemeral_public = PublicKey::from(ephemeral_bytes);
+    let nonce = &wrapped_key[aad.len()..WRAPPED_KEY_HEADER_LEN];
+    let sealed_key = &wrapped_key[WRAPPED_KEY_HEADER_LEN..];
+
+    let shared = recipient.diffie_hellman(&ephemeral_public);
+    let cipher = wrapping_cipher(&shared, &ephemeral_public, &PublicKey::from(recipient));
+    let key = cipher
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: sealed_key,
+                aad,
+            },
+        )
+        .map_err(|_| EnvelopeError::Decryption)?;
+
+    key.try_into().map_err(|_| EnvelopeError::Malformed)
+}
+
+fn wrapping_cipher(
+    shared: &SharedSecret,
+    ephemeral_public: &PublicKey,
+    recipient: &PublicKey,
+) -> XChaCha20Poly1305 {
+    let mut salt = [0; 2 * X25519_KEY_LEN];
+    salt[..X25519_KEY_LEN].copy_from_slice(ephemeral_public.as_bytes());
+    salt[X25519_KEY_LEN..].copy_from_slice(recipient.as_bytes());
+    let mut key = [0; DATA_KEY_LEN];
+    Hkdf::<Sha256>::new(Some(&salt), shared.as_bytes())
+        .expand(WRAP_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+
+    XChaCha20Poly1305::new(&key.into())
+}
+
+fn check_version(version: u8) -> Result<(), EnvelopeError> {
+    if version == ENVELOPE_VERSION {
+        Ok(())
+    } else {
+        Err(EnvelopeError::UnsupportedVersion(version))
+    }
+}
+
+fn random_nonce() -> [u8; NONCE_LEN] {
+    let mut nonce = [0; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    nonce
+}
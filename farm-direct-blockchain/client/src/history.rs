@@ -0,0 +1,105 @@
+// farm-direct-blockchain/client/src/history.rs
+//
+// Replays a product's history chain off-chain. Entries come from the
+// `HistoryRecorded` events in transaction logs; for a product migrated from an
+// older layout, the log starts with the growth updates and then the delivery
+// updates the account held when it was migrated (see `migrated_entries`).
+use std::fmt;
+
+use farm_direct_blockchain::{history_genesis, HistoryEntry, HistoryRecorded, ProductCycle};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryError {
+    // The event at this index does not extend the chain before it
+    BrokenLink(usize),
+    // The replayed chain ends somewhere other than the account's `history_hash`
+    HeadMismatch,
+    // The log and the account disagree on the stored growth or delivery updates
+    LogMismatch,
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BrokenLink(index) => write!(f, "history chain broken at entry {index}"),
+            Self::HeadMismatch => write!(f, "history does not end at the product's hash"),
+            Self::LogMismatch => write!(f, "history does not match the product's logs"),
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+pub fn replay(product_id: &str, entries: &[HistoryEntry]) -> [u8; 32] {
+    entries
+        .iter()
+        .fold(history_genesis(product_id), |prev, entry| {
+            entry.chain(&prev)
+        })
+}
+
+/// Checks that each event's hash follows from the ones before it, and returns
+/// the head of the chain.
+pub fn verify_events(
+    product_id: &str,
+    events: &[HistoryRecorded],
+) -> Result<[u8; 32], HistoryError> {
+    let mut head = history_genesis(product_id);
+    for (index, event) in events.iter().enumerate() {
+        head = event.entry.chain(&head);
+        if head != event.history_hash {
+            return Err(HistoryError::BrokenLink(index));
+        }
+    }
+
+    Ok(head)
+}
+
+/// Checks that `entries` hash to the product's `history_hash` and account for
+/// every growth and delivery update the product holds, in order.
+pub fn verify_product(
+    product: &ProductCycle,
+    entries: &[HistoryEntry],
+) -> Result<(), HistoryError> {
+    if replay(&product.product_id, entries) != product.history_hash {
+        return Err(HistoryError::HeadMismatch);
+    }
+
+    let growth = entries.iter().filter_map(|entry| match entry {
+        HistoryEntry::Growth(update) => Some(update),
+        _ => None,
+    });
+    let delivery = entries.iter().filter_map(|entry| match entry {
+        HistoryEntry::Delivery(update) => Some(update),
+        _ => None,
+    });
+    if !growth.eq(product.growth_updates.iter()) || !delivery.eq(product.delivery_updates.iter()) {
+        return Err(HistoryError::LogMismatch);
+    }
+
+    Ok(())
+}
+
+/// The entries a migration folded into the chain, given the growth and delivery
+/// updates the account held at the time.
+pub fn migrated_entries(
+    product: &ProductCycle,
+    growth_count: usize,
+    delivery_count: usize,
+) -> Vec<HistoryEntry> {
+    product
+        .growth_updates
+        .iter()
+        .take(growth_count)
+        .cloned()
+        .map(HistoryEntry::Growth)
+        .chain(
+            product
+                .delivery_updates
+                .iter()
+                .take(delivery_count)
+                .cloned()
+                .map(HistoryEntry::Delivery),
+        )
+        .collect()
+}
@@ -0,0 +1,5 @@
+// farm-direct-blockchain/client/src/lib.rs
+//
+// Off-chain helpers for clients of the farm-direct program.
+pub mod envelope;
+pub mod history;
@@ -0,0 +1,61 @@
+use farm_direct_blockchain::envelope::{is_valid_profile_data, is_valid_wrapped_key};
+use farm_direct_client::envelope::{
+    generate_data_key, generate_encryption_key, open_profile_data, seal_profile_data,
+    unwrap_data_key, wrap_data_key, EnvelopeError,
+};
+
+#[test]
+fn profile_data_round_trips() {
+    let data_key = generate_data_key();
+    let sealed = seal_profile_data(&data_key, b"+48 600 100 200, ul. Polna 1");
+
+    assert!(is_valid_profile_data(&sealed));
+    assert_eq!(
+        open_profile_data(&data_key, &sealed).unwrap(),
+        b"+48 600 100 200, ul. Polna 1"
+    );
+    assert_eq!(
+        open_profile_data(&generate_data_key(), &sealed),
+        Err(EnvelopeError::Decryption)
+    );
+}
+
+#[test]
+fn buyer_unwraps_the_data_key() {
+    let data_key = generate_data_key();
+    let (buyer_secret, buyer_public) = generate_encryption_key();
+    let wrapped = wrap_data_key(&data_key, &buyer_public);
+
+    assert!(is_valid_wrapped_key(&wrapped));
+    assert_eq!(unwrap_data_key(&wrapped, &buyer_secret).unwrap(), data_key);
+
+    let (stranger, _) = generate_encryption_key();
+    assert_eq!(
+        unwrap_data_key(&wrapped, &stranger),
+        Err(EnvelopeError::Decryption)
+    );
+}
+
+#[test]
+fn rejects_tampered_envelopes() {
+    let data_key = generate_data_key();
+    let (buyer_secret, buyer_public) = generate_encryption_key();
+
+    let mut wrapped = wrap_data_key(&data_key, &buyer_public);
+    wrapped[5] ^= 1;
+    assert_eq!(
+        unwrap_data_key(&wrapped, &buyer_secret),
+        Err(EnvelopeError::Decryption)
+    );
+
+    wrapped[0] = 2;
+    assert_eq!(
+        unwrap_data_key(&wrapped, &buyer_secret),
+        Err(EnvelopeError::UnsupportedVersion(2))
+    );
+
+    assert_eq!(
+        open_profile_data(&data_key, "enc:v0:deadbeef"),
+        Err(EnvelopeError::Malformed)
+    );
+}
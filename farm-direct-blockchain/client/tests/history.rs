@@ -0,0 +1,121 @@
+use anchor_lang::prelude::Pubkey;
+use farm_direct_blockchain::migration::upgrade_product_cycle;
+use farm_direct_blockchain::{
+    DeliveryStatus, DeliveryUpdate, HistoryEntry, HistoryRecorded, ProductCycle,
+};
+use farm_direct_client::history::{
+    migrated_entries, replay, verify_events, verify_product, HistoryError,
+};
+
+const PRODUCT_CYCLE_V1: &[u8] =
+    include_bytes!("../../programs/farm-direct-blockchain/tests/fixtures/product_cycle_v1.bin");
+
+fn migrated_product() -> ProductCycle {
+    upgrade_product_cycle(PRODUCT_CYCLE_V1, &Pubkey::new_from_array([7; 32])).unwrap()
+}
+
+// Applies entries the way the program does, returning the events it would log
+fn record(product: &mut ProductCycle, entries: Vec<HistoryEntry>) -> Vec<HistoryRecorded> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            product.history_hash = entry.chain(&product.history_hash);
+            match &entry {
+                HistoryEntry::Growth(update) => product.growth_updates.push(update.clone()),
+                HistoryEntry::Delivery(update) => product.delivery_updates.push(update.clone()),
+                HistoryEntry::ActualQuantity { quantity, .. } => {
+                    product.actual_quantity = *quantity
+                }
+                HistoryEntry::Lot { .. }
+                | HistoryEntry::Split { .. }
+                | HistoryEntry::Merged { .. } => {}
+            }
+            HistoryRecorded {
+                product: Pubkey::default(),
+                entry,
+                history_hash: product.history_hash,
+            }
+        })
+        .collect()
+}
+
+fn delivered(timestamp: i64) -> HistoryEntry {
+    HistoryEntry::Delivery(DeliveryUpdate {
+        status: DeliveryStatus::Completed,
+        timestamp,
+        notes: String::new(),
+        location: None,
+        checkpoint: None,
+        signed_by: Pubkey::new_from_array([7; 32]),
+    })
+}
+
+#[test]
+fn replays_a_migrated_product_and_later_events() {
+    let mut product = migrated_product();
+    let (growth, delivery) = (product.growth_updates.len(), product.delivery_updates.len());
+    let mut entries = migrated_entries(&product, growth, delivery);
+    assert_eq!(verify_product(&product, &entries), Ok(()));
+
+    let events = record(
+        &mut product,
+        vec![
+            HistoryEntry::ActualQuantity {
+                quantity: 480,
+                timestamp: 1_723_000_000,
+            },
+            delivered(1_723_100_000),
+        ],
+    );
+    let seed = replay(&product.product_id, &entries);
+    assert_eq!(events[0].entry.chain(&seed), events[0].history_hash);
+
+    entries.extend(events.into_iter().map(|event| event.entry));
+    assert_eq!(verify_product(&product, &entries), Ok(()));
+}
+
+#[test]
+fn detects_edited_or_dropped_entries() {
+    let mut product = migrated_product();
+    product.history_hash = replay(&product.product_id, &[]);
+    product.growth_updates.clear();
+    product.delivery_updates.clear();
+    let mut events = record(
+        &mut product,
+        vec![delivered(1_723_100_000), delivered(1_723_200_000)],
+    );
+    assert_eq!(
+        verify_events(&product.product_id, &events),
+        Ok(product.history_hash)
+    );
+
+    if let HistoryEntry::Delivery(update) = &mut events[0].entry {
+        update.notes.push_str("edited");
+    }
+    assert_eq!(
+        verify_events(&product.product_id, &events),
+        Err(HistoryError::BrokenLink(0))
+    );
+
+    let entries = [delivered(1_723_200_000)];
+    assert_eq!(
+        verify_product(&product, &entries),
+        Err(HistoryError::HeadMismatch)
+    );
+}
+
+#[test]
+fn detects_an_account_rewritten_after_the_fact() {
+    let mut product = migrated_product();
+    let entries = migrated_entries(
+        &product,
+        product.growth_updates.len(),
+        product.delivery_updates.len(),
+    );
+
+    product.delivery_updates.pop();
+    assert_eq!(
+        verify_product(&product, &entries),
+        Err(HistoryError::LogMismatch)
+    );
+}
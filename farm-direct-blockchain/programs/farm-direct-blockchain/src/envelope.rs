@@ -0,0 +1,55 @@
+// farm-direct-blockchain/programs/farm-direct-blockchain/src/envelope.rs
+//
+// Wire format for farmer personal data. The program only checks shape; sealing
+// and opening happen off-chain (see the `farm-direct-client` crate).
+//
+// Profile data is encrypted once under a random 32-byte data key with
+// XChaCha20-Poly1305 and stored in `FarmerProfile.encrypted_data` as
+//
+//     "enc:v1:" || base64(version || nonce[24] || ciphertext || tag[16])
+//
+// The data key is shared with a buyer by wrapping it to their X25519 key:
+//
+//     version || ephemeral_public[32] || nonce[24] || sealed_key[32] || tag[16]
+//
+// where the wrapping key is HKDF-SHA256 over the X25519 shared secret, salted
+// with `ephemeral_public || recipient_public` and using `WRAP_INFO`. The header
+// (version and, for wrapped keys, the ephemeral key) is the AEAD associated data.
+
+pub const ENVELOPE_VERSION: u8 = 1;
+pub const PROFILE_DATA_PREFIX: &str = "enc:v1:";
+pub const WRAP_INFO: &[u8] = b"farm-direct/envelope/v1/wrap";
+
+pub const DATA_KEY_LEN: usize = 32;
+pub const X25519_KEY_LEN: usize = 32;
+pub const NONCE_LEN: usize = 24;
+pub const TAG_LEN: usize = 16;
+
+pub const PROFILE_DATA_HEADER_LEN: usize = 1 + NONCE_LEN;
+pub const WRAPPED_KEY_HEADER_LEN: usize = 1 + X25519_KEY_LEN + NONCE_LEN;
+pub const WRAPPED_KEY_LEN: usize = WRAPPED_KEY_HEADER_LEN + DATA_KEY_LEN + TAG_LEN;
+
+pub fn is_valid_wrapped_key(wrapped_key: &[u8]) -> bool {
+    wrapped_key.len() == WRAPPED_KEY_LEN && wrapped_key[0] == ENVELOPE_VERSION
+}
+
+// Checks the prefix and base64 shape without decoding. The version byte is
+// covered by the AEAD tag, so a forged one fails to open rather than parse.
+pub fn is_valid_profile_data(encrypted_data: &str) -> bool {
+    let Some(encoded) = encrypted_data.strip_prefix(PROFILE_DATA_PREFIX) else {
+        return false;
+    };
+    let bytes = encoded.as_bytes();
+    if bytes.is_empty() || bytes.len() % 4 != 0 {
+        return false;
+    }
+
+    let padding = bytes.iter().rev().take_while(|&&b| b == b'=').count();
+    let is_base64 = padding <= 2
+        && bytes[..bytes.len() - padding]
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || *b == b'+' || *b == b'/');
+    let decoded_len = bytes.len() / 4 * 3 - padding;
+
+    is_base64 && decoded_len >= PROFILE_DATA_HEADER_LEN + TAG_LEN
+}
@@ -1,9 +1,89 @@
 // farm-direct-blockchain/programs/farm-direct-blockchain/src/lib.rs - Fixed Version
+// Anchor's generated IDL handlers still call `AccountInfo::realloc`, deprecated
+// since solana-program 2.2 (pulled in by anchor-spl).
+#![allow(deprecated)]
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::hash::{hash, hashv};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount};
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_2022_extensions::spl_pod::optional_keys::OptionalNonZeroPubkey;
+use anchor_spl::token_2022_extensions::spl_token_metadata_interface::state::{
+    Field, TokenMetadata,
+};
+use anchor_spl::token_2022_extensions::{
+    token_metadata_initialize, token_metadata_update_field, TokenMetadataInitialize,
+    TokenMetadataUpdateField,
+};
+use anchor_spl::token_interface::{self, Token2022};
+
+pub mod envelope;
+pub mod migration;
 
 declare_id!("9n3L3af5CKKPqdUXjCFBnt5kto95tqCjZv9vANECuS4V");
 
+// Current layout version of each account type. Once a layout has been
+// deployed, bump the matching constant and add a snapshot of it to `migration`
+// before changing its fields.
+pub const FARMER_PROFILE_VERSION: u8 = 2;
+pub const PRODUCT_CYCLE_VERSION: u8 = 2;
+pub const CAMPAIGN_VERSION: u8 = 2;
+
+// Buyers can reclaim a pre-order in full if no harvest is recorded this long
+// after the estimated harvest date.
+pub const PRE_ORDER_REFUND_GRACE_PERIOD: i64 = 30 * 24 * 60 * 60;
+
+// A campaign deadline can be pushed back once, by at most this much.
+pub const MAX_DEADLINE_EXTENSION: i64 = 30 * 24 * 60 * 60;
+
+pub const BATCH_CERTIFICATE_SYMBOL: &str = "FARM";
+
+// Farm locations are public, so they are capped at roughly 1.2 km precision.
+// Delivery checkpoints may use full geohash precision.
+pub const MAX_FARM_GEOHASH_LEN: usize = 6;
+pub const MAX_CHECKPOINT_GEOHASH_LEN: usize = 12;
+
+// Media is pinned by content hash, so only stores whose URIs we can resolve
+// for auditors are accepted.
+pub const MEDIA_URI_PREFIXES: [&str; 3] = [
+    "ipfs://",
+    "ar://",
+    "https://firebasestorage.googleapis.com/",
+];
+pub const MEDIA_MIME_TYPES: [&str; 5] = [
+    "image/jpeg",
+    "image/png",
+    "image/webp",
+    "image/heic",
+    "video/mp4",
+];
+pub const MAX_MEDIA_URI_LEN: usize = 128;
+pub const MAX_MEDIA_PER_ENTRY: usize = 4;
+
+pub const MAX_STAFF_KEYS: usize = 10;
+
+// A harvest can be divided between up to this many buyers at once, and a
+// cooperative can pool up to this many lots into one.
+pub const MAX_SPLIT_CHILDREN: usize = 8;
+pub const MAX_MERGE_PARENTS: usize = 8;
+
+pub const MAX_COOP_NAME_LEN: usize = 64;
+pub const MAX_COOP_MEMBERS: usize = 32;
+pub const MAX_COOP_CONTRIBUTORS: usize = 16;
+
+pub const MAX_CAMPAIGN_BENEFICIARIES: usize = 8;
+
+pub const MAX_STRETCH_GOALS: usize = 4;
+pub const MAX_STRETCH_GOAL_MILESTONE_LEN: usize = 64;
+
+// Keeps a provenance proof inside the 1 KiB return data limit.
+pub const MAX_PROOF_CERTIFICATIONS: usize = 4;
+pub const MAX_PROOF_GROWTH_STAGES: usize = 8;
+pub const MAX_PROOF_DELIVERY_STEPS: usize = 6;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const MAX_PLATFORM_FEE_BPS: u16 = 1_000;
+
 #[program]
 pub mod farm_direct_blockchain {
     use super::*;
@@ -13,21 +93,50 @@ pub mod farm_direct_blockchain {
         encrypted_data: String,
         public_name: String,
         region: String,
+        region_code: String,
+        geohash: Option<String>,
         certifications: Vec<String>,
     ) -> Result<()> {
         let farmer_profile = &mut ctx.accounts.farmer_profile;
         let clock = Clock::get()?;
 
+        require!(
+            is_valid_encrypted_data(&encrypted_data),
+            ErrorCode::InvalidEnvelope
+        );
+        require!(is_valid_region_code(&region_code), ErrorCode::InvalidRegionCode);
+        if let Some(hash) = &geohash {
+            require!(
+                is_valid_geohash(hash, MAX_FARM_GEOHASH_LEN),
+                ErrorCode::InvalidGeohash
+            );
+        }
+
+        farmer_profile.version = FARMER_PROFILE_VERSION;
         farmer_profile.farmer = ctx.accounts.farmer.key();
         farmer_profile.encrypted_data = encrypted_data;
         farmer_profile.public_name = public_name;
         farmer_profile.region = region;
+        farmer_profile.region_code = region_code;
+        farmer_profile.geohash = geohash;
         farmer_profile.certifications = certifications;
         farmer_profile.verification_status = false;
         farmer_profile.reputation_score = 0;
         farmer_profile.total_products = 0;
         farmer_profile.created_at = clock.unix_timestamp;
         farmer_profile.updated_at = clock.unix_timestamp;
+        farmer_profile.active_products = 0;
+        farmer_profile.active_campaigns = 0;
+        farmer_profile.anonymised = false;
+        farmer_profile.active_grants = 0;
+
+        // A closed profile cannot be recreated to shed its history
+        let tombstone_info = ctx.accounts.farmer_tombstone.to_account_info();
+        if let Some(tombstone) = read_optional_pda::<FarmerTombstone>(&tombstone_info)? {
+            farmer_profile.reputation_score = tombstone.reputation_score;
+            farmer_profile.total_products = tombstone.total_products;
+            close_program_account(&tombstone_info, &ctx.accounts.farmer.to_account_info())?;
+        }
 
         Ok(())
     }
@@ -43,6 +152,7 @@ pub mod farm_direct_blockchain {
         let clock = Clock::get()?;
 
         if let Some(data) = encrypted_data {
+            require!(is_valid_encrypted_data(&data), ErrorCode::InvalidEnvelope);
             farmer_profile.encrypted_data = data;
         }
         if let Some(name) = public_name {
@@ -60,37 +170,89 @@ pub mod farm_direct_blockchain {
         Ok(())
     }
 
+    pub fn set_farm_location(
+        ctx: Context<UpdateFarmerProfile>,
+        region_code: String,
+        geohash: Option<String>,
+    ) -> Result<()> {
+        let farmer_profile = &mut ctx.accounts.farmer_profile;
+        let clock = Clock::get()?;
+
+        require!(is_valid_region_code(&region_code), ErrorCode::InvalidRegionCode);
+        if let Some(hash) = &geohash {
+            require!(
+                is_valid_geohash(hash, MAX_FARM_GEOHASH_LEN),
+                ErrorCode::InvalidGeohash
+            );
+        }
+
+        farmer_profile.region_code = region_code;
+        farmer_profile.geohash = geohash;
+        farmer_profile.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn create_product(
         ctx: Context<CreateProduct>,
         product_name: String,
-        category: String,
+        category: ProductCategory,
+        subcategory: Option<ProductSubcategory>,
         description: String,
         estimated_harvest_date: i64,
         estimated_quantity: u64,
-        firebase_image_urls: Vec<String>,
+        media: Vec<MediaRef>,
     ) -> Result<()> {
         let product_cycle = &mut ctx.accounts.product_cycle;
         let farmer_profile = &mut ctx.accounts.farmer_profile;
         let clock = Clock::get()?;
 
-        let mut seed = ctx.accounts.farmer.key().to_bytes().to_vec();
-        seed.extend_from_slice(&clock.unix_timestamp.to_le_bytes());
-        product_cycle.product_id = hash(&seed).to_string();
+        require_farmer_or_staff(
+            &ctx.accounts.farmer.key(),
+            &ctx.accounts.authority.key(),
+            ctx.accounts.delegate.as_deref(),
+            StaffPermission::ProductCreation,
+            clock.unix_timestamp,
+        )?;
+        if let Some(sub) = subcategory {
+            require!(sub.category() == category, ErrorCode::InvalidSubcategory);
+        }
+        require!(is_valid_media(&media), ErrorCode::InvalidMediaRef);
+
+        product_cycle.product_id =
+            new_product_id(&product_cycle.key(), farmer_profile.total_products);
+        product_cycle.history_hash = history_genesis(&product_cycle.product_id);
 
+        product_cycle.version = PRODUCT_CYCLE_VERSION;
         product_cycle.farmer = ctx.accounts.farmer.key();
         product_cycle.product_name = product_name;
         product_cycle.category = category;
+        product_cycle.subcategory = subcategory;
         product_cycle.description = description;
         product_cycle.estimated_harvest_date = estimated_harvest_date;
         product_cycle.estimated_quantity = estimated_quantity;
         product_cycle.actual_quantity = 0;
-        product_cycle.firebase_image_urls = firebase_image_urls;
+        product_cycle.firebase_image_urls = Vec::new();
+        product_cycle.media = media;
         product_cycle.growth_updates = Vec::new();
         product_cycle.delivery_updates = Vec::new();
         product_cycle.created_at = clock.unix_timestamp;
         product_cycle.updated_at = clock.unix_timestamp;
+        // Unpriced and out of stock until set_price and adjust_stock
+        product_cycle.price_per_unit = 0;
+        product_cycle.price_mint = None;
+        product_cycle.unit = UnitOfMeasure::Kilogram;
+        product_cycle.available_stock = 0;
+        product_cycle.min_order_quantity = 1;
+        product_cycle.parents = Vec::new();
+        product_cycle.lot_status = LotStatus::Active;
+        product_cycle.share_campaigns = 0;
+        product_cycle.active_subscriptions = 0;
+        product_cycle.rent_payer = ctx.accounts.authority.key();
 
         farmer_profile.total_products += 1;
+        farmer_profile.active_products += 1;
         farmer_profile.updated_at = clock.unix_timestamp;
 
         Ok(())
@@ -100,59 +262,264 @@ pub mod farm_direct_blockchain {
         ctx: Context<UpdateProduct>,
         stage: GrowthStage,
         notes: String,
-        firebase_image_urls: Vec<String>,
+        media: Vec<MediaRef>,
     ) -> Result<()> {
         let product_cycle = &mut ctx.accounts.product_cycle;
         let clock = Clock::get()?;
 
+        require_farmer_or_staff(
+            &product_cycle.farmer,
+            &ctx.accounts.authority.key(),
+            ctx.accounts.delegate.as_deref(),
+            StaffPermission::GrowthUpdates,
+            clock.unix_timestamp,
+        )?;
+        require!(is_valid_media(&media), ErrorCode::InvalidMediaRef);
+
         let growth_update = GrowthUpdate {
             stage,
             timestamp: clock.unix_timestamp,
             notes,
-            firebase_image_urls,
+            firebase_image_urls: Vec::new(),
+            media,
         };
 
+        record_history(
+            product_cycle.key(),
+            product_cycle,
+            HistoryEntry::Growth(growth_update.clone()),
+        );
         product_cycle.growth_updates.push(growth_update);
         product_cycle.updated_at = clock.unix_timestamp;
 
         Ok(())
     }
 
-    pub fn update_actual_quantity(
+    pub fn set_price(
         ctx: Context<UpdateProduct>,
+        price_per_unit: u64,
+        price_mint: Option<Pubkey>,
+        unit: UnitOfMeasure,
+        min_order_quantity: u64,
+    ) -> Result<()> {
+        let product_cycle = &mut ctx.accounts.product_cycle;
+        let clock = Clock::get()?;
+
+        require_keys_eq!(
+            product_cycle.farmer,
+            ctx.accounts.authority.key(),
+            ErrorCode::UnauthorizedFarmer
+        );
+        product_cycle.set_pricing(price_per_unit, price_mint, unit, min_order_quantity)?;
+        product_cycle.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn adjust_stock(ctx: Context<UpdateProduct>, delta: i64) -> Result<()> {
+        let product_cycle = &mut ctx.accounts.product_cycle;
+        let clock = Clock::get()?;
+
+        require_keys_eq!(
+            product_cycle.farmer,
+            ctx.accounts.authority.key(),
+            ErrorCode::UnauthorizedFarmer
+        );
+        product_cycle.adjust_stock(delta)?;
+        product_cycle.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn update_actual_quantity(
+        ctx: Context<UpdateActualQuantity>,
         actual_quantity: u64,
     ) -> Result<()> {
         let product_cycle = &mut ctx.accounts.product_cycle;
         let clock = Clock::get()?;
 
+        // Child lots were sized from the recorded harvest
+        require!(
+            product_cycle.lot_status == LotStatus::Active,
+            ErrorCode::LotNotActive
+        );
         product_cycle.actual_quantity = actual_quantity;
         product_cycle.updated_at = clock.unix_timestamp;
+        record_history(
+            product_cycle.key(),
+            product_cycle,
+            HistoryEntry::ActualQuantity {
+                quantity: actual_quantity,
+                timestamp: clock.unix_timestamp,
+            },
+        );
+
+        // The first recorded harvest fixes how much of the pre-order book is filled
+        if let Some(listing) = ctx.accounts.pre_order_listing.as_mut() {
+            if listing.harvested_quantity.is_none() {
+                listing.harvested_quantity = Some(actual_quantity);
+                listing.is_open = false;
+                listing.updated_at = clock.unix_timestamp;
+            }
+        }
 
         Ok(())
     }
 
     pub fn add_delivery_update(
-        ctx: Context<UpdateProduct>,
+        ctx: Context<AddDeliveryUpdate>,
         status: DeliveryStatus,
         notes: String,
         location: Option<String>,
+        checkpoint: Option<GeoCheckpoint>,
     ) -> Result<()> {
         let product_cycle = &mut ctx.accounts.product_cycle;
         let clock = Clock::get()?;
+        let signer = ctx.accounts.authority.key();
+
+        // Settlements are never paused, like `settle_reservation`
+        let settles_instalment =
+            status == DeliveryStatus::Completed && ctx.accounts.subscription.is_some();
+        require!(
+            settles_instalment
+                || !ctx
+                    .accounts
+                    .platform_config
+                    .is_paused(PausableFeature::ProfileWrites),
+            ErrorCode::ProgramPaused
+        );
+
+        // Couriers report handoffs, and only the courier or the recipient can
+        // mark an order delivered; everything else is the farmer's to post.
+        let assignment = ctx.accounts.courier_assignment.as_deref();
+        let is_courier = assignment.is_some_and(|a| a.courier == signer);
+        match status {
+            DeliveryStatus::Delivered => {
+                let is_recipient = assignment.and_then(|a| a.recipient) == Some(signer)
+                    || ctx
+                        .accounts
+                        .subscription
+                        .as_ref()
+                        .is_some_and(|sub| sub.buyer == signer);
+                require!(
+                    is_courier || is_recipient,
+                    ErrorCode::UnauthorizedDeliveryConfirmation
+                );
+            }
+            DeliveryStatus::InTransit if is_courier => {}
+            _ => require_farmer_or_staff(
+                &product_cycle.farmer,
+                &signer,
+                ctx.accounts.delegate.as_deref(),
+                StaffPermission::DeliveryUpdates,
+                clock.unix_timestamp,
+            )?,
+        }
+        if is_courier {
+            require!(checkpoint.is_some(), ErrorCode::CheckpointRequired);
+        }
+        if status == DeliveryStatus::Completed {
+            require!(
+                product_cycle.awaiting_completion(),
+                ErrorCode::DeliveryNotConfirmed
+            );
+        }
+        if let Some(point) = &checkpoint {
+            require!(
+                is_valid_geohash(&point.geohash, MAX_CHECKPOINT_GEOHASH_LEN),
+                ErrorCode::InvalidGeohash
+            );
+            require!(
+                point.recorded_at <= clock.unix_timestamp,
+                ErrorCode::InvalidCheckpointTime
+            );
+        }
+
+        // A courier delivering to someone else doesn't confirm the subscriber's order
+        if status == DeliveryStatus::Delivered {
+            if let Some(subscription) = ctx.accounts.subscription.as_mut() {
+                let recipient = assignment.and_then(|a| a.recipient);
+                require!(
+                    signer == subscription.buyer
+                        || recipient.is_none_or(|r| r == subscription.buyer),
+                    ErrorCode::UnauthorizedDeliveryConfirmation
+                );
+                subscription.confirm_delivery();
+                subscription.updated_at = clock.unix_timestamp;
+            }
+        }
+
+        // Completing a confirmed delivery against a subscription releases one instalment
+        if status == DeliveryStatus::Completed {
+            if let Some(subscription) = ctx.accounts.subscription.as_mut() {
+                let (Some(vault), Some(treasury), Some(system_program)) = (
+                    ctx.accounts.subscription_vault.as_ref(),
+                    ctx.accounts.treasury.as_ref(),
+                    ctx.accounts.system_program.as_ref(),
+                ) else {
+                    return err!(ErrorCode::MissingSubscriptionAccounts);
+                };
+                let platform_config = &ctx.accounts.platform_config;
+                require!(subscription.is_active, ErrorCode::SubscriptionNotActive);
+                require!(
+                    subscription.release_instalment(),
+                    ErrorCode::DeliveryNotConfirmed
+                );
+                if !subscription.is_active {
+                    product_cycle.active_subscriptions =
+                        product_cycle.active_subscriptions.saturating_sub(1);
+                }
+
+                let subscription_key = subscription.key();
+                let vault_seeds: &[&[u8]] = &[
+                    b"subscription_vault",
+                    subscription_key.as_ref(),
+                    &[subscription.vault_bump],
+                ];
+                let fee = pay_farmer_from_vault(
+                    vault,
+                    &ctx.accounts.farmer.to_account_info(),
+                    treasury,
+                    system_program,
+                    &[vault_seeds],
+                    subscription.price_per_delivery,
+                    platform_config.order_fee_bps,
+                )?;
+
+                emit!(PlatformFeeCollected {
+                    kind: FeeKind::SubscriptionInstalment,
+                    source: subscription_key,
+                    farmer: subscription.farmer,
+                    gross_amount: subscription.price_per_delivery,
+                    fee_amount: fee,
+                    timestamp: clock.unix_timestamp,
+                });
+
+                subscription.updated_at = clock.unix_timestamp;
+            }
+        }
 
         let delivery_update = DeliveryUpdate {
             status,
             timestamp: clock.unix_timestamp,
             notes,
             location,
+            checkpoint,
+            signed_by: signer,
         };
 
+        record_history(
+            product_cycle.key(),
+            product_cycle,
+            HistoryEntry::Delivery(delivery_update.clone()),
+        );
         product_cycle.delivery_updates.push(delivery_update);
         product_cycle.updated_at = clock.unix_timestamp;
 
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_crowdfunding_campaign(
         ctx: Context<CreateCrowdfundingCampaign>,
         title: String,
@@ -161,14 +528,40 @@ pub mod farm_direct_blockchain {
         deadline: i64,
         campaign_type: CampaignType,
         milestones: Vec<String>,
+        beneficiaries: Vec<Beneficiary>,
+        funding: FundingOptions,
     ) -> Result<()> {
         let campaign = &mut ctx.accounts.campaign;
         let clock = Clock::get()?;
 
+        // No beneficiaries means the farmer alone; sharing is for neighbours
+        // buying equipment together
+        let beneficiaries = if beneficiaries.is_empty() {
+            vec![Beneficiary {
+                wallet: ctx.accounts.farmer.key(),
+                share_bps: BPS_DENOMINATOR as u16,
+            }]
+        } else {
+            beneficiaries
+        };
+        require!(
+            is_valid_beneficiaries(&beneficiaries),
+            ErrorCode::InvalidBeneficiaries
+        );
+        require!(
+            beneficiaries.len() == 1 || campaign_type == CampaignType::Equipment,
+            ErrorCode::JointCampaignNotSupported
+        );
+        require!(
+            is_valid_funding(goal_amount, &funding),
+            ErrorCode::InvalidFundingOptions
+        );
+
         let mut seed = ctx.accounts.farmer.key().to_bytes().to_vec();
         seed.extend_from_slice(&clock.unix_timestamp.to_le_bytes());
         campaign.campaign_id = hash(&seed).to_string();
 
+        campaign.version = CAMPAIGN_VERSION;
         campaign.farmer = ctx.accounts.farmer.key();
         campaign.title = title;
         campaign.description = description;
@@ -181,6 +574,26 @@ pub mod farm_direct_blockchain {
         campaign.is_active = true;
         campaign.created_at = clock.unix_timestamp;
         campaign.updated_at = clock.unix_timestamp;
+        campaign.is_cancelled = false;
+        campaign.deadline_extended = false;
+        campaign.total_withdrawn = 0;
+        campaign.beneficiaries = beneficiaries;
+        campaign.allow_overfunding = funding.allow_overfunding;
+        campaign.hard_cap = funding.hard_cap;
+        campaign.stretch_goals = funding.stretch_goals;
+        campaign.stretch_goals_reached = 0;
+
+        let farmer_profile = &mut ctx.accounts.farmer_profile;
+        farmer_profile.active_campaigns += 1;
+        farmer_profile.updated_at = clock.unix_timestamp;
+
+        // Keep the vault rent-exempt so individual refunds never strand it below the minimum
+        let ix = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.farmer.to_account_info(),
+            to: ctx.accounts.campaign_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), ix);
+        anchor_lang::system_program::transfer(cpi_ctx, Rent::get()?.minimum_balance(0))?;
 
         Ok(())
     }
@@ -192,6 +605,15 @@ pub mod farm_direct_blockchain {
         let campaign = &mut ctx.accounts.campaign;
         let clock = Clock::get()?;
 
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(campaign.is_active, ErrorCode::CampaignNotActive);
+        require!(
+            clock.unix_timestamp <= campaign.deadline,
+            ErrorCode::CampaignDeadlineExceeded
+        );
+        let wallet = ctx.accounts.contributor.key();
+        let newly_reached = campaign.record_contribution(wallet, amount, clock.unix_timestamp)?;
+
         let ix = anchor_lang::system_program::Transfer {
             from: ctx.accounts.contributor.to_account_info(),
             to: ctx.accounts.campaign_vault.to_account_info(),
@@ -199,217 +621,4218 @@ pub mod farm_direct_blockchain {
         let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), ix);
         anchor_lang::system_program::transfer(cpi_ctx, amount)?;
 
-        let contributor = Contributor {
-            wallet: ctx.accounts.contributor.key(),
-            amount,
-            timestamp: clock.unix_timestamp,
+        for index in newly_reached {
+            let goal = &campaign.stretch_goals[index as usize];
+            emit!(StretchGoalReached {
+                campaign: campaign.key(),
+                index,
+                amount: goal.amount,
+                milestone: goal.milestone.clone(),
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn migrate_farmer_profile(ctx: Context<MigrateFarmerProfile>) -> Result<()> {
+        let profile_info = ctx.accounts.farmer_profile.to_account_info();
+        let upgraded = migration::upgrade_farmer_profile(
+            &profile_info.try_borrow_data()?,
+            &ctx.accounts.farmer.key(),
+        )?;
+
+        migration::write_upgraded(
+            &profile_info,
+            &ctx.accounts.farmer,
+            &ctx.accounts.system_program,
+            FarmerProfile::SPACE,
+            &upgraded,
+        )
+    }
+
+    pub fn migrate_product(ctx: Context<MigrateProduct>) -> Result<()> {
+        let product_info = ctx.accounts.product_cycle.to_account_info();
+        let upgraded = migration::upgrade_product_cycle(
+            &product_info.try_borrow_data()?,
+            &ctx.accounts.farmer.key(),
+        )?;
+
+        migration::write_upgraded(
+            &product_info,
+            &ctx.accounts.farmer,
+            &ctx.accounts.system_program,
+            ProductCycle::SPACE,
+            &upgraded,
+        )
+    }
+
+    pub fn migrate_campaign(ctx: Context<MigrateCampaign>) -> Result<()> {
+        let campaign_info = ctx.accounts.campaign.to_account_info();
+        let upgraded = migration::upgrade_campaign(
+            &campaign_info.try_borrow_data()?,
+            &ctx.accounts.farmer.key(),
+        )?;
+
+        migration::write_upgraded(
+            &campaign_info,
+            &ctx.accounts.farmer,
+            &ctx.accounts.system_program,
+            CrowdfundingCampaign::SPACE,
+            &upgraded,
+        )
+    }
+
+    pub fn open_pre_order(
+        ctx: Context<OpenPreOrder>,
+        price_per_unit: u64,
+        reservable_quantity: u64,
+    ) -> Result<()> {
+        let product_cycle = &ctx.accounts.product_cycle;
+        let clock = Clock::get()?;
+
+        require!(price_per_unit > 0, ErrorCode::InvalidAmount);
+        require!(
+            reservable_quantity > 0 && reservable_quantity <= product_cycle.estimated_quantity,
+            ErrorCode::InvalidAmount
+        );
+        require!(
+            product_cycle.actual_quantity == 0,
+            ErrorCode::HarvestAlreadyRecorded
+        );
+        require!(
+            product_cycle.estimated_harvest_date > clock.unix_timestamp,
+            ErrorCode::PreOrderClosed
+        );
+
+        // Keep the vault rent-exempt so partial payouts never strand it below the minimum
+        let ix = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.farmer.to_account_info(),
+            to: ctx.accounts.pre_order_vault.to_account_info(),
         };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), ix);
+        anchor_lang::system_program::transfer(cpi_ctx, Rent::get()?.minimum_balance(0))?;
 
-        campaign.contributors.push(contributor);
-        campaign.current_amount += amount;
-        campaign.updated_at = clock.unix_timestamp;
+        let listing = &mut ctx.accounts.pre_order_listing;
+        listing.product = product_cycle.key();
+        listing.farmer = ctx.accounts.farmer.key();
+        listing.price_per_unit = price_per_unit;
+        listing.reservable_quantity = reservable_quantity;
+        listing.reserved_quantity = 0;
+        listing.harvested_quantity = None;
+        listing.estimated_harvest_date = product_cycle.estimated_harvest_date;
+        listing.is_open = true;
+        listing.created_at = clock.unix_timestamp;
+        listing.updated_at = clock.unix_timestamp;
+        listing.bump = ctx.bumps.pre_order_listing;
+        listing.vault_bump = ctx.bumps.pre_order_vault;
+        listing.open_reservations = 0;
+
+        Ok(())
+    }
+
+    pub fn reserve_pre_order(ctx: Context<ReservePreOrder>, quantity: u64) -> Result<()> {
+        let listing = &mut ctx.accounts.pre_order_listing;
+        let clock = Clock::get()?;
+
+        require!(quantity > 0, ErrorCode::InvalidAmount);
+        require!(
+            listing.is_open
+                && listing.harvested_quantity.is_none()
+                && clock.unix_timestamp < listing.estimated_harvest_date,
+            ErrorCode::PreOrderClosed
+        );
+
+        let reserved_quantity = listing
+            .reserved_quantity
+            .checked_add(quantity)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        require!(
+            reserved_quantity <= listing.reservable_quantity,
+            ErrorCode::ExceedsReservableQuantity
+        );
+        let amount = quantity
+            .checked_mul(listing.price_per_unit)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        let ix = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.buyer.to_account_info(),
+            to: ctx.accounts.pre_order_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), ix);
+        anchor_lang::system_program::transfer(cpi_ctx, amount)?;
+
+        listing.reserved_quantity = reserved_quantity;
+        listing.open_reservations += 1;
+        listing.updated_at = clock.unix_timestamp;
+
+        let reservation = &mut ctx.accounts.reservation;
+        reservation.listing = listing.key();
+        reservation.buyer = ctx.accounts.buyer.key();
+        reservation.quantity = quantity;
+        reservation.amount_paid = amount;
+        reservation.created_at = clock.unix_timestamp;
+        reservation.bump = ctx.bumps.reservation;
+
+        Ok(())
+    }
+
+    pub fn settle_reservation(ctx: Context<SettleReservation>) -> Result<()> {
+        let listing = &mut ctx.accounts.pre_order_listing;
+        let reservation = &ctx.accounts.reservation;
+        let clock = Clock::get()?;
+
+        let (payment, refund) = listing.settlement(reservation, clock.unix_timestamp)?;
 
-        if campaign.current_amount >= campaign.goal_amount {
-            campaign.is_active = false;
+        let listing_key = listing.key();
+        let vault_seeds: &[&[u8]] = &[b"preorder_vault", listing_key.as_ref(), &[listing.vault_bump]];
+        if payment > 0 {
+            let fee = pay_farmer_from_vault(
+                &ctx.accounts.pre_order_vault,
+                &ctx.accounts.farmer,
+                &ctx.accounts.treasury,
+                &ctx.accounts.system_program,
+                &[vault_seeds],
+                payment,
+                ctx.accounts.platform_config.order_fee_bps,
+            )?;
+
+            emit!(PlatformFeeCollected {
+                kind: FeeKind::PreOrderSettlement,
+                source: listing_key,
+                farmer: listing.farmer,
+                gross_amount: payment,
+                fee_amount: fee,
+                timestamp: clock.unix_timestamp,
+            });
         }
+        transfer_from_vault(
+            &ctx.accounts.pre_order_vault,
+            &ctx.accounts.buyer,
+            &ctx.accounts.system_program,
+            &[vault_seeds],
+            refund,
+        )?;
+
+        listing.open_reservations -= 1;
+        listing.updated_at = clock.unix_timestamp;
 
         Ok(())
     }
-}
 
-// Data structures
-#[account]
-pub struct FarmerProfile {
-    pub farmer: Pubkey,                    // Changed from farmer_wallet to farmer
-    pub encrypted_data: String,
-    pub public_name: String,
-    pub region: String,
-    pub certifications: Vec<String>,
-    pub verification_status: bool,
-    pub reputation_score: u64,
-    pub total_products: u64,
-    pub created_at: i64,
-    pub updated_at: i64,
-}
+    pub fn create_subscription(
+        ctx: Context<CreateSubscription>,
+        price_per_delivery: u64,
+        total_deliveries: u32,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
 
-#[account]
-pub struct ProductCycle {
-    pub product_id: String,
-    pub farmer: Pubkey,
-    pub product_name: String,
-    pub category: String,
-    pub description: String,
-    pub estimated_harvest_date: i64,
-    pub estimated_quantity: u64,
-    pub actual_quantity: u64,
-    pub firebase_image_urls: Vec<String>,
-    pub growth_updates: Vec<GrowthUpdate>,
-    pub delivery_updates: Vec<DeliveryUpdate>,
-    pub created_at: i64,
-    pub updated_at: i64,
-}
+        require!(
+            price_per_delivery > 0 && total_deliveries > 0,
+            ErrorCode::InvalidAmount
+        );
+        let prepaid = price_per_delivery
+            .checked_mul(total_deliveries as u64)
+            .ok_or(ErrorCode::InvalidAmount)?;
 
-#[account]
-pub struct CrowdfundingCampaign {
-    pub campaign_id: String,
-    pub farmer: Pubkey,
-    pub title: String,
-    pub description: String,
-    pub goal_amount: u64,
-    pub current_amount: u64,
-    pub deadline: i64,
-    pub campaign_type: CampaignType,
-    pub milestones: Vec<String>,
-    pub contributors: Vec<Contributor>,
-    pub is_active: bool,
-    pub created_at: i64,
-    pub updated_at: i64,
-}
+        // The rent reserve keeps the vault alive between instalments and is refunded on cancel
+        let ix = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.buyer.to_account_info(),
+            to: ctx.accounts.subscription_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), ix);
+        anchor_lang::system_program::transfer(cpi_ctx, prepaid + Rent::get()?.minimum_balance(0))?;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct GrowthUpdate {
-    pub stage: GrowthStage,
-    pub timestamp: i64,
-    pub notes: String,
-    pub firebase_image_urls: Vec<String>,
-}
+        // The product stays open until every subscription on it has ended
+        let product_cycle = &mut ctx.accounts.product_cycle;
+        product_cycle.active_subscriptions = product_cycle
+            .active_subscriptions
+            .checked_add(1)
+            .ok_or(ErrorCode::InvalidAmount)?;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct DeliveryUpdate {
-    pub status: DeliveryStatus,
-    pub timestamp: i64,
-    pub notes: String,
-    pub location: Option<String>,
-}
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.farmer = ctx.accounts.farmer_profile.farmer;
+        subscription.buyer = ctx.accounts.buyer.key();
+        subscription.product_cycle = ctx.accounts.product_cycle.key();
+        subscription.price_per_delivery = price_per_delivery;
+        subscription.total_deliveries = total_deliveries;
+        subscription.delivered_count = 0;
+        subscription.confirmed_count = 0;
+        subscription.is_active = true;
+        subscription.created_at = clock.unix_timestamp;
+        subscription.updated_at = clock.unix_timestamp;
+        subscription.bump = ctx.bumps.subscription;
+        subscription.vault_bump = ctx.bumps.subscription_vault;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct Contributor {
-    pub wallet: Pubkey,
-    pub amount: u64,
-    pub timestamp: i64,
-}
+        Ok(())
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
-pub enum GrowthStage {
-    Seeding,
-    Germination,
-    Growing,
-    Flowering,
-    Fruiting,
-    Harvest,
-    PostHarvest,
-}
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        let subscription = &ctx.accounts.subscription;
+        let refund = subscription.cancellation_refund(Rent::get()?.minimum_balance(0))?;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
-pub enum DeliveryStatus {
-    Preparing,
-    Packed,
-    InTransit,
-    Delivered,
-    Completed,
-}
+        // A finished subscription no longer counts against its product, which
+        // may be gone by now
+        if subscription.is_active {
+            let Some(product_cycle) = ctx.accounts.product_cycle.as_mut() else {
+                return err!(ErrorCode::SubscriptionProductMismatch);
+            };
+            product_cycle.active_subscriptions =
+                product_cycle.active_subscriptions.saturating_sub(1);
+        }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
-pub enum CampaignType {
-    Equipment,
-    Seeds,
-    Infrastructure,
-    Expansion,
-    Emergency,
-}
+        let subscription_key = subscription.key();
+        let vault_seeds: &[&[u8]] = &[
+            b"subscription_vault",
+            subscription_key.as_ref(),
+            &[subscription.vault_bump],
+        ];
+        transfer_from_vault(
+            &ctx.accounts.subscription_vault,
+            &ctx.accounts.buyer.to_account_info(),
+            &ctx.accounts.system_program,
+            &[vault_seeds],
+            refund,
+        )
+    }
 
-#[derive(Accounts)]
-pub struct InitializeFarmer<'info> {
-    #[account(
-        init, 
-        payer = farmer, 
-        space = 8 + 32 + 256 + 128 + 64 + 512 + 1 + 8 + 8 + 8 + 8,
-        seeds = [b"farmer_profile", farmer.key().as_ref()],
-        bump
-    )]
-    pub farmer_profile: Account<'info, FarmerProfile>,
-    #[account(mut)]
-    pub farmer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    pub fn enable_harvest_shares(ctx: Context<EnableHarvestShares>) -> Result<()> {
+        require!(
+            matches!(
+                ctx.accounts.campaign.campaign_type,
+                CampaignType::Seeds | CampaignType::Expansion
+            ),
+            ErrorCode::HarvestSharesNotSupported
+        );
 
-#[derive(Accounts)]
-pub struct UpdateFarmerProfile<'info> {
-    #[account(
-        mut, 
-        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
-        seeds = [b"farmer_profile", farmer.key().as_ref()],
-        bump
-    )]
-    pub farmer_profile: Account<'info, FarmerProfile>,
-    #[account(mut)]
-    pub farmer: Signer<'info>,
-}
+        // The product has to outlive the shares redeemed against it
+        let product_cycle = &mut ctx.accounts.product_cycle;
+        product_cycle.share_campaigns = product_cycle
+            .share_campaigns
+            .checked_add(1)
+            .ok_or(ErrorCode::InvalidAmount)?;
 
-#[derive(Accounts)]
-pub struct CreateProduct<'info> {
-    #[account(
-        init, 
+        let harvest_shares = &mut ctx.accounts.harvest_shares;
+        harvest_shares.campaign = ctx.accounts.campaign.key();
+        harvest_shares.product_cycle = ctx.accounts.product_cycle.key();
+        harvest_shares.share_mint = ctx.accounts.share_mint.key();
+        harvest_shares.total_shares = 0;
+        harvest_shares.redeemed_shares = 0;
+        harvest_shares.redeemed_quantity = 0;
+        harvest_shares.bump = ctx.bumps.harvest_shares;
+
+        Ok(())
+    }
+
+    pub fn claim_harvest_shares(ctx: Context<ClaimHarvestShares>) -> Result<()> {
+        let campaign = &ctx.accounts.campaign;
+        let contributor = ctx.accounts.contributor.key();
+        let clock = Clock::get()?;
+
+        // Shares are only fixed once the campaign has stopped taking contributions
+        require!(
+            !campaign.is_open(clock.unix_timestamp),
+            ErrorCode::CampaignStillActive
+        );
+        require!(!campaign.is_cancelled, ErrorCode::CampaignCancelled);
+        // A failed campaign is refunded instead
+        require!(
+            campaign.current_amount >= campaign.goal_amount,
+            ErrorCode::CampaignGoalNotReached
+        );
+
+        // One share per contributed lamport, summed over every contribution
+        let shares = campaign
+            .contributors
+            .iter()
+            .filter(|c| c.wallet == contributor)
+            .map(|c| c.amount)
+            .sum::<u64>();
+        require!(shares > 0, ErrorCode::NotAContributor);
+
+        let campaign_key = campaign.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"harvest_shares",
+            campaign_key.as_ref(),
+            &[ctx.accounts.harvest_shares.bump],
+        ]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                to: ctx.accounts.contributor_token_account.to_account_info(),
+                authority: ctx.accounts.harvest_shares.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::mint_to(cpi_ctx, shares)?;
+
+        ctx.accounts.harvest_shares.snapshot_total(campaign.current_amount);
+
+        let share_claim = &mut ctx.accounts.share_claim;
+        share_claim.campaign = campaign_key;
+        share_claim.contributor = contributor;
+        share_claim.shares = shares;
+        share_claim.claimed_at = clock.unix_timestamp;
+        share_claim.bump = ctx.bumps.share_claim;
+
+        Ok(())
+    }
+
+    pub fn redeem_harvest_shares(ctx: Context<RedeemHarvestShares>, shares: u64) -> Result<()> {
+        let product_cycle = &ctx.accounts.product_cycle;
+        let clock = Clock::get()?;
+
+        require!(shares > 0, ErrorCode::InvalidAmount);
+        require!(
+            product_cycle.actual_quantity > 0,
+            ErrorCode::HarvestNotRecorded
+        );
+
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                from: ctx.accounts.holder_token_account.to_account_info(),
+                authority: ctx.accounts.holder.to_account_info(),
+            },
+        );
+        token::burn(cpi_ctx, shares)?;
+
+        let harvest_shares = &mut ctx.accounts.harvest_shares;
+        let quantity = harvest_shares.quantity_for(shares, product_cycle.actual_quantity);
+        harvest_shares.redeemed_shares += shares;
+        harvest_shares.redeemed_quantity += quantity;
+
+        emit!(HarvestSharesRedeemed {
+            campaign: harvest_shares.campaign,
+            holder: ctx.accounts.holder.key(),
+            shares,
+            quantity,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn mint_batch_certificate(ctx: Context<MintBatchCertificate>, uri: String) -> Result<()> {
+        let product_cycle = &ctx.accounts.product_cycle;
+        let mint_info = ctx.accounts.certificate_mint.to_account_info();
+        let token_program = ctx.accounts.token_program.to_account_info();
+
+        require!(
+            product_cycle.actual_quantity > 0,
+            ErrorCode::HarvestNotRecorded
+        );
+
+        let product_key = product_cycle.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"batch_certificate",
+            product_key.as_ref(),
+            &[ctx.bumps.certificate_mint],
+        ]];
+
+        let additional_metadata = product_cycle.certificate_metadata(&product_key);
+        let metadata = TokenMetadata {
+            update_authority: OptionalNonZeroPubkey(mint_info.key()),
+            mint: mint_info.key(),
+            name: product_cycle.product_name.clone(),
+            symbol: BATCH_CERTIFICATE_SYMBOL.to_string(),
+            uri: uri.clone(),
+            additional_metadata: additional_metadata.clone(),
+        };
+
+        // Token-2022 reallocates the mint for the metadata but expects the rent up front
+        let data_len = mint_info.data_len() + metadata.tlv_size_of()?;
+        let rent_due = Rent::get()?
+            .minimum_balance(data_len)
+            .saturating_sub(mint_info.lamports());
+        let ix = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.farmer.to_account_info(),
+            to: mint_info.clone(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), ix);
+        anchor_lang::system_program::transfer(cpi_ctx, rent_due)?;
+
+        token_metadata_initialize(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                TokenMetadataInitialize {
+                    program_id: token_program.clone(),
+                    mint: mint_info.clone(),
+                    metadata: mint_info.clone(),
+                    mint_authority: mint_info.clone(),
+                    update_authority: mint_info.clone(),
+                },
+                signer_seeds,
+            ),
+            metadata.name,
+            metadata.symbol,
+            uri,
+        )?;
+        for (key, value) in additional_metadata {
+            token_metadata_update_field(
+                CpiContext::new_with_signer(
+                    token_program.clone(),
+                    TokenMetadataUpdateField {
+                        program_id: token_program.clone(),
+                        metadata: mint_info.clone(),
+                        update_authority: mint_info.clone(),
+                    },
+                    signer_seeds,
+                ),
+                Field::Key(key),
+                value,
+            )?;
+        }
+
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                token_interface::MintTo {
+                    mint: mint_info.clone(),
+                    to: ctx.accounts.farmer_token_account.to_account_info(),
+                    authority: mint_info.clone(),
+                },
+                signer_seeds,
+            ),
+            1,
+        )?;
+
+        // Revoke minting so the certificate stays one of a kind
+        token_interface::set_authority(
+            CpiContext::new_with_signer(
+                token_program,
+                token_interface::SetAuthority {
+                    current_authority: mint_info.clone(),
+                    account_or_mint: mint_info,
+                },
+                signer_seeds,
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )
+    }
+
+    pub fn initialize_platform_config(
+        ctx: Context<InitializePlatformConfig>,
+        campaign_fee_bps: u16,
+        order_fee_bps: u16,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(
+            is_valid_fee_bps(campaign_fee_bps) && is_valid_fee_bps(order_fee_bps),
+            ErrorCode::FeeTooHigh
+        );
+
+        // Keep the treasury rent-exempt so fees of any size can land in it
+        let ix = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.admin.to_account_info(),
+            to: ctx.accounts.treasury.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), ix);
+        anchor_lang::system_program::transfer(cpi_ctx, Rent::get()?.minimum_balance(0))?;
+
+        let platform_config = &mut ctx.accounts.platform_config;
+        platform_config.admin = ctx.accounts.admin.key();
+        platform_config.guardian = ctx.accounts.admin.key();
+        platform_config.campaign_fee_bps = campaign_fee_bps;
+        platform_config.order_fee_bps = order_fee_bps;
+        platform_config.paused = false;
+        platform_config.crowdfunding_paused = false;
+        platform_config.orders_paused = false;
+        platform_config.profile_writes_paused = false;
+        platform_config.created_at = clock.unix_timestamp;
+        platform_config.updated_at = clock.unix_timestamp;
+        platform_config.bump = ctx.bumps.platform_config;
+        platform_config.treasury_bump = ctx.bumps.treasury;
+
+        Ok(())
+    }
+
+    pub fn update_platform_config(
+        ctx: Context<UpdatePlatformConfig>,
+        campaign_fee_bps: Option<u16>,
+        order_fee_bps: Option<u16>,
+        admin: Option<Pubkey>,
+        guardian: Option<Pubkey>,
+    ) -> Result<()> {
+        let platform_config = &mut ctx.accounts.platform_config;
+        let clock = Clock::get()?;
+
+        if let Some(bps) = campaign_fee_bps {
+            require!(is_valid_fee_bps(bps), ErrorCode::FeeTooHigh);
+            platform_config.campaign_fee_bps = bps;
+        }
+        if let Some(bps) = order_fee_bps {
+            require!(is_valid_fee_bps(bps), ErrorCode::FeeTooHigh);
+            platform_config.order_fee_bps = bps;
+        }
+        if let Some(new_admin) = admin {
+            platform_config.admin = new_admin;
+        }
+        if let Some(new_guardian) = guardian {
+            platform_config.guardian = new_guardian;
+        }
+
+        platform_config.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn set_paused(
+        ctx: Context<SetPaused>,
+        feature: Option<PausableFeature>,
+        paused: bool,
+    ) -> Result<()> {
+        let platform_config = &mut ctx.accounts.platform_config;
+        let clock = Clock::get()?;
+
+        platform_config.set_pause_flag(feature, paused);
+        platform_config.updated_at = clock.unix_timestamp;
+
+        emit!(PauseToggled {
+            feature,
+            paused,
+            authority: ctx.accounts.authority.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        let treasury = &ctx.accounts.treasury;
+        let clock = Clock::get()?;
+
+        let available = treasury
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(amount > 0 && amount <= available, ErrorCode::InvalidAmount);
+
+        let treasury_seeds: &[&[u8]] = &[b"treasury", &[ctx.accounts.platform_config.treasury_bump]];
+        transfer_from_vault(
+            treasury,
+            &ctx.accounts.recipient,
+            &ctx.accounts.system_program,
+            &[treasury_seeds],
+            amount,
+        )?;
+
+        emit!(TreasuryWithdrawn {
+            admin: ctx.accounts.admin.key(),
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Beneficiaries other than the farmer are passed as remaining accounts, in
+    // the campaign's order.
+    pub fn withdraw_campaign_funds<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawCampaignFunds<'info>>,
+    ) -> Result<()> {
+        let campaign = &mut ctx.accounts.campaign;
+        let clock = Clock::get()?;
+
+        require!(!campaign.is_cancelled, ErrorCode::CampaignCancelled);
+        require!(
+            campaign.current_amount >= campaign.goal_amount,
+            ErrorCode::CampaignGoalNotReached
+        );
+
+        let amount = ctx
+            .accounts
+            .campaign_vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let campaign_key = campaign.key();
+        let vault_seeds: &[&[u8]] = &[
+            b"campaign_vault",
+            campaign_key.as_ref(),
+            &[ctx.bumps.campaign_vault],
+        ];
+        let (fee, net) =
+            split_platform_fee(amount, ctx.accounts.platform_config.campaign_fee_bps);
+        transfer_from_vault(
+            &ctx.accounts.campaign_vault,
+            &ctx.accounts.treasury,
+            &ctx.accounts.system_program,
+            &[vault_seeds],
+            fee,
+        )?;
+
+        let farmer = ctx.accounts.farmer.to_account_info();
+        let mut others = ctx.remaining_accounts.iter();
+        for (beneficiary, payout) in campaign
+            .beneficiaries
+            .iter()
+            .zip(campaign.payouts(net))
+        {
+            let wallet = if beneficiary.wallet == farmer.key() {
+                &farmer
+            } else {
+                others.next().ok_or(ErrorCode::InvalidBeneficiaries)?
+            };
+            require_keys_eq!(wallet.key(), beneficiary.wallet, ErrorCode::InvalidBeneficiaries);
+            transfer_from_vault(
+                &ctx.accounts.campaign_vault,
+                wallet,
+                &ctx.accounts.system_program,
+                &[vault_seeds],
+                payout,
+            )?;
+        }
+
+        campaign.total_withdrawn += amount;
+        campaign.updated_at = clock.unix_timestamp;
+
+        emit!(PlatformFeeCollected {
+            kind: FeeKind::CampaignWithdrawal,
+            source: campaign_key,
+            farmer: campaign.farmer,
+            gross_amount: amount,
+            fee_amount: fee,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_campaign(ctx: Context<CancelCampaign>) -> Result<()> {
+        let shares_info = ctx.accounts.harvest_shares.to_account_info();
+        let harvest_shares = read_optional_pda::<HarvestShares>(&shares_info)?;
+        let campaign = &mut ctx.accounts.campaign;
+        let clock = Clock::get()?;
+
+        campaign.check_cancellable(harvest_shares.as_ref())?;
+
+        campaign.is_cancelled = true;
+        campaign.is_active = false;
+        campaign.updated_at = clock.unix_timestamp;
+
+        emit!(CampaignCancelled {
+            campaign: campaign.key(),
+            farmer: campaign.farmer,
+            refundable_amount: campaign.current_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_campaign_details(
+        ctx: Context<ManageCampaign>,
+        title: Option<String>,
+        description: Option<String>,
+        milestones: Option<Vec<String>>,
+    ) -> Result<()> {
+        let campaign = &mut ctx.accounts.campaign;
+        let clock = Clock::get()?;
+
+        require!(!campaign.is_cancelled, ErrorCode::CampaignCancelled);
+        require!(
+            campaign.contributors.is_empty() && campaign.current_amount == 0,
+            ErrorCode::CampaignHasContributions
+        );
+
+        if let Some(t) = title {
+            campaign.title = t;
+        }
+        if let Some(desc) = description {
+            campaign.description = desc;
+        }
+        if let Some(m) = milestones {
+            campaign.milestones = m;
+        }
+
+        campaign.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn extend_deadline(ctx: Context<ManageCampaign>, new_deadline: i64) -> Result<()> {
+        let campaign = &mut ctx.accounts.campaign;
+        let clock = Clock::get()?;
+
+        require!(campaign.is_active, ErrorCode::CampaignNotActive);
+        require!(
+            clock.unix_timestamp <= campaign.deadline,
+            ErrorCode::CampaignDeadlineExceeded
+        );
+        require!(!campaign.deadline_extended, ErrorCode::DeadlineAlreadyExtended);
+        require!(
+            new_deadline > campaign.deadline
+                && new_deadline - campaign.deadline <= MAX_DEADLINE_EXTENSION,
+            ErrorCode::InvalidDeadlineExtension
+        );
+
+        let previous_deadline = campaign.deadline;
+        campaign.deadline = new_deadline;
+        campaign.deadline_extended = true;
+        campaign.updated_at = clock.unix_timestamp;
+
+        emit!(CampaignDeadlineExtended {
+            campaign: campaign.key(),
+            previous_deadline,
+            new_deadline,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        let campaign = &mut ctx.accounts.campaign;
+        let contributor = ctx.accounts.contributor.key();
+        let clock = Clock::get()?;
+
+        let claimed_shares = !ctx.accounts.share_claim.data_is_empty();
+        let refund = campaign.refund_for(&contributor, clock.unix_timestamp, claimed_shares)?;
+
+        let campaign_key = campaign.key();
+        let vault_seeds: &[&[u8]] = &[
+            b"campaign_vault",
+            campaign_key.as_ref(),
+            &[ctx.bumps.campaign_vault],
+        ];
+        transfer_from_vault(
+            &ctx.accounts.campaign_vault,
+            &ctx.accounts.contributor.to_account_info(),
+            &ctx.accounts.system_program,
+            &[vault_seeds],
+            refund,
+        )?;
+
+        campaign.contributors.retain(|c| c.wallet != contributor);
+        campaign.current_amount -= refund;
+        campaign.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Closes the product's pre-order listing and coop listing along with it;
+    // a courier assignment has to be released first.
+    pub fn close_product(ctx: Context<CloseProduct>) -> Result<()> {
+        let farmer = ctx.accounts.farmer.to_account_info();
+        let clock = Clock::get()?;
+
+        let listing_info = ctx.accounts.pre_order_listing.to_account_info();
+        let listing = read_optional_pda::<PreOrderListing>(&listing_info)?;
+        ctx.accounts.product_cycle.check_closable(
+            !ctx.accounts.certificate_mint.data_is_empty(),
+            !ctx.accounts.courier_assignment.data_is_empty(),
+            listing.as_ref(),
+        )?;
+
+        if let Some(listing) = listing {
+            // Only the rent reserve the farmer put in is left
+            let vault = ctx.accounts.pre_order_vault.to_account_info();
+            let vault_seeds: &[&[u8]] = &[
+                b"preorder_vault",
+                listing_info.key.as_ref(),
+                &[listing.vault_bump],
+            ];
+            transfer_from_vault(
+                &vault,
+                &farmer,
+                &ctx.accounts.system_program,
+                &[vault_seeds],
+                vault.lamports(),
+            )?;
+            close_program_account(&listing_info, &farmer)?;
+        }
+
+        let coop_info = ctx.accounts.coop_product.to_account_info();
+        if let Some(coop_product) = read_optional_pda::<CoopProduct>(&coop_info)? {
+            // Members' proceeds must be paid out before the rent reserve goes back
+            let vault = ctx.accounts.coop_vault.to_account_info();
+            require!(
+                vault.lamports() <= Rent::get()?.minimum_balance(0),
+                ErrorCode::CoopProceedsUndistributed
+            );
+            let vault_seeds: &[&[u8]] = &[
+                b"coop_vault",
+                coop_info.key.as_ref(),
+                &[coop_product.vault_bump],
+            ];
+            transfer_from_vault(
+                &vault,
+                &farmer,
+                &ctx.accounts.system_program,
+                &[vault_seeds],
+                vault.lamports(),
+            )?;
+            close_program_account(&coop_info, &farmer)?;
+
+            // The coop admin lists coop products, so the cooperative is theirs
+            let cooperative_info = ctx.accounts.cooperative.to_account_info();
+            if let Some(mut cooperative) = read_optional_pda::<Cooperative>(&cooperative_info)? {
+                require_keys_eq!(
+                    cooperative_info.key(),
+                    coop_product.cooperative,
+                    ErrorCode::UnauthorizedCoopAdmin
+                );
+                cooperative.close_contributions(&coop_product.contributions);
+                cooperative.updated_at = clock.unix_timestamp;
+                cooperative.try_serialize(&mut &mut cooperative_info.try_borrow_mut_data()?[..])?;
+            }
+        }
+
+        let farmer_profile = &mut ctx.accounts.farmer_profile;
+        farmer_profile.active_products = farmer_profile.active_products.saturating_sub(1);
+        farmer_profile.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn close_campaign(ctx: Context<CloseCampaign>) -> Result<()> {
+        let campaign = &ctx.accounts.campaign;
+        let clock = Clock::get()?;
+
+        require!(
+            campaign.is_cancelled || !campaign.is_active || clock.unix_timestamp > campaign.deadline,
+            ErrorCode::CampaignStillActive
+        );
+        // Backers claim shares against the contributor list stored here
+        require!(
+            ctx.accounts.harvest_shares.data_is_empty(),
+            ErrorCode::CampaignHasHarvestShares
+        );
+
+        // Only the rent reserve may be left once refunds or the payout are done
+        let vault = &ctx.accounts.campaign_vault;
+        require!(
+            vault.lamports() <= Rent::get()?.minimum_balance(0),
+            ErrorCode::CampaignNotSettled
+        );
+
+        let campaign_key = campaign.key();
+        let vault_seeds: &[&[u8]] = &[
+            b"campaign_vault",
+            campaign_key.as_ref(),
+            &[ctx.bumps.campaign_vault],
+        ];
+        transfer_from_vault(
+            vault,
+            &ctx.accounts.farmer.to_account_info(),
+            &ctx.accounts.system_program,
+            &[vault_seeds],
+            vault.lamports(),
+        )?;
+
+        let farmer_profile = &mut ctx.accounts.farmer_profile;
+        farmer_profile.active_campaigns = farmer_profile.active_campaigns.saturating_sub(1);
+        farmer_profile.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn close_farmer_profile(ctx: Context<CloseFarmerProfile>) -> Result<()> {
+        let farmer_profile = &ctx.accounts.farmer_profile;
+        let clock = Clock::get()?;
+
+        require!(
+            farmer_profile.active_products == 0 && farmer_profile.active_campaigns == 0,
+            ErrorCode::FarmerHasActiveListings
+        );
+
+        let tombstone = &mut ctx.accounts.farmer_tombstone;
+        tombstone.farmer = farmer_profile.farmer;
+        tombstone.reputation_score = farmer_profile.reputation_score;
+        tombstone.total_products = farmer_profile.total_products;
+        tombstone.closed_at = clock.unix_timestamp;
+        tombstone.bump = ctx.bumps.farmer_tombstone;
+
+        Ok(())
+    }
+
+    pub fn assign_courier(
+        ctx: Context<AssignCourier>,
+        courier: Pubkey,
+        recipient: Option<Pubkey>,
+    ) -> Result<()> {
+        let assignment = &mut ctx.accounts.courier_assignment;
+        let clock = Clock::get()?;
+
+        require_keys_neq!(courier, ctx.accounts.farmer.key(), ErrorCode::InvalidCourier);
+
+        assignment.product = ctx.accounts.product_cycle.key();
+        assignment.farmer = ctx.accounts.farmer.key();
+        assignment.courier = courier;
+        assignment.recipient = recipient;
+        assignment.assigned_at = clock.unix_timestamp;
+        assignment.updated_at = clock.unix_timestamp;
+        assignment.bump = ctx.bumps.courier_assignment;
+
+        Ok(())
+    }
+
+    // Hands the order to another courier; earlier updates keep their signer
+    pub fn reassign_courier(
+        ctx: Context<ManageCourier>,
+        courier: Pubkey,
+        recipient: Option<Pubkey>,
+    ) -> Result<()> {
+        let assignment = &mut ctx.accounts.courier_assignment;
+        let clock = Clock::get()?;
+
+        require_keys_neq!(courier, ctx.accounts.farmer.key(), ErrorCode::InvalidCourier);
+
+        assignment.courier = courier;
+        assignment.recipient = recipient;
+        assignment.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Returns the assignment's rent once the delivery is done
+    pub fn release_courier(_ctx: Context<ReleaseCourier>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn initialize_delegate(ctx: Context<InitializeDelegate>) -> Result<()> {
+        let delegate = &mut ctx.accounts.delegate;
+        let clock = Clock::get()?;
+
+        delegate.farmer = ctx.accounts.farmer.key();
+        delegate.staff = Vec::new();
+        delegate.created_at = clock.unix_timestamp;
+        delegate.updated_at = clock.unix_timestamp;
+        delegate.bump = ctx.bumps.delegate;
+
+        Ok(())
+    }
+
+    // Adds a staff key, or replaces the permissions and expiry of an existing one
+    pub fn set_staff_key(
+        ctx: Context<ManageDelegate>,
+        key: Pubkey,
+        permissions: StaffPermissions,
+        expires_at: i64,
+    ) -> Result<()> {
+        let delegate = &mut ctx.accounts.delegate;
+        let clock = Clock::get()?;
+
+        require!(expires_at > clock.unix_timestamp, ErrorCode::InvalidStaffExpiry);
+        require_keys_neq!(key, delegate.farmer, ErrorCode::InvalidStaffKey);
+
+        let staff_key = StaffKey {
+            key,
+            permissions,
+            expires_at,
+        };
+        match delegate.staff.iter_mut().find(|staff| staff.key == key) {
+            Some(existing) => *existing = staff_key,
+            None => {
+                // Expired entries are pruned before refusing a new key
+                let now = clock.unix_timestamp;
+                delegate.staff.retain(|staff| staff.expires_at > now);
+                require!(
+                    delegate.staff.len() < MAX_STAFF_KEYS,
+                    ErrorCode::TooManyStaffKeys
+                );
+                delegate.staff.push(staff_key);
+            }
+        }
+        delegate.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn remove_staff_key(ctx: Context<ManageDelegate>, key: Pubkey) -> Result<()> {
+        let delegate = &mut ctx.accounts.delegate;
+        let clock = Clock::get()?;
+
+        let before = delegate.staff.len();
+        delegate.staff.retain(|staff| staff.key != key);
+        require!(delegate.staff.len() < before, ErrorCode::StaffKeyNotFound);
+        delegate.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Publishes the profile data key wrapped to a buyer's X25519 key
+    pub fn grant_access(
+        ctx: Context<GrantAccess>,
+        buyer: Pubkey,
+        buyer_encryption_key: [u8; 32],
+        wrapped_key: Vec<u8>,
+    ) -> Result<()> {
+        let grant = &mut ctx.accounts.access_grant;
+        let clock = Clock::get()?;
+
+        require!(
+            envelope::is_valid_wrapped_key(&wrapped_key),
+            ErrorCode::InvalidEnvelope
+        );
+
+        grant.farmer = ctx.accounts.farmer.key();
+        grant.buyer = buyer;
+        grant.buyer_encryption_key = buyer_encryption_key;
+        grant.wrapped_key = wrapped_key;
+        grant.created_at = clock.unix_timestamp;
+        grant.bump = ctx.bumps.access_grant;
+
+        let farmer_profile = &mut ctx.accounts.farmer_profile;
+        farmer_profile.active_grants += 1;
+        farmer_profile.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Closing the grant stops future reads; a buyer who already unwrapped the
+    // key keeps it until the farmer re-encrypts under a new data key.
+    pub fn revoke_access(ctx: Context<RevokeAccess>) -> Result<()> {
+        let farmer_profile = &mut ctx.accounts.farmer_profile;
+        farmer_profile.active_grants = farmer_profile.active_grants.saturating_sub(1);
+        farmer_profile.updated_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Wipes personal fields and closes the access grants passed as remaining
+    // accounts. Counters and reputation stay so listings and reviews still add
+    // up; earlier values remain in ledger history, which the program cannot
+    // rewrite, but the data behind `encrypted_data` is unreadable once the
+    // farmer discards the data key.
+    pub fn erase_personal_data<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ErasePersonalData<'info>>,
+    ) -> Result<()> {
+        let farmer_profile = &mut ctx.accounts.farmer_profile;
+        let farmer = ctx.accounts.farmer.to_account_info();
+        let clock = Clock::get()?;
+
+        let mut grants_revoked: u32 = 0;
+        for grant_info in ctx.remaining_accounts {
+            require_keys_eq!(
+                *grant_info.owner,
+                crate::ID,
+                anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram
+            );
+            require!(
+                grant_info.is_writable,
+                anchor_lang::error::ErrorCode::ConstraintMut
+            );
+            let grant = AccessGrant::try_deserialize(&mut &grant_info.try_borrow_data()?[..])?;
+            require_keys_eq!(grant.farmer, farmer.key(), ErrorCode::UnauthorizedFarmer);
+
+            close_program_account(grant_info, &farmer)?;
+            farmer_profile.active_grants = farmer_profile.active_grants.saturating_sub(1);
+            grants_revoked += 1;
+        }
+
+        farmer_profile.anonymise(clock.unix_timestamp)?;
+
+        emit!(PersonalDataErased {
+            farmer: farmer.key(),
+            grants_revoked,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Read-only; simulate it to check a product from its QR code
+    pub fn verify_provenance(ctx: Context<VerifyProvenance>) -> Result<ProvenanceProof> {
+        Ok(ProvenanceProof::new(
+            ctx.accounts.product_cycle.key(),
+            &ctx.accounts.product_cycle,
+            &ctx.accounts.farmer_profile,
+        ))
+    }
+
+    // Divides a harvested lot into child lots, one per quantity. The children
+    // are passed as remaining accounts at [b"lot", parent, index].
+    pub fn split_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SplitBatch<'info>>,
+        quantities: Vec<u64>,
+    ) -> Result<()> {
+        let parent_key = ctx.accounts.product_cycle.key();
+        let listing_info = ctx.accounts.pre_order_listing.to_account_info();
+        let pre_order = read_optional_pda::<PreOrderListing>(&listing_info)?;
+        let parent = &mut ctx.accounts.product_cycle;
+        let farmer_profile = &mut ctx.accounts.farmer_profile;
+        let clock = Clock::get()?;
+
+        parent.check_divisible(pre_order.as_ref())?;
+        require!(
+            quantities.len() == ctx.remaining_accounts.len(),
+            ErrorCode::InvalidSplit
+        );
+        require!(
+            is_valid_split(&quantities, parent.actual_quantity),
+            ErrorCode::InvalidSplit
+        );
+
+        let mut lots = Vec::with_capacity(quantities.len());
+        for (index, (child_info, quantity)) in
+            ctx.remaining_accounts.iter().zip(quantities).enumerate()
+        {
+            let index = [index as u8];
+            let (address, bump) = Pubkey::find_program_address(
+                &[b"lot", parent_key.as_ref(), &index],
+                ctx.program_id,
+            );
+            require_keys_eq!(child_info.key(), address, ErrorCode::InvalidLotAccount);
+            create_program_account(
+                child_info,
+                &ctx.accounts.farmer,
+                &ctx.accounts.system_program,
+                ProductCycle::SPACE,
+                &[&[b"lot", parent_key.as_ref(), &index, &[bump]]],
+            )?;
+
+            let origin = LotParent {
+                product: parent_key,
+                history_hash: parent.history_hash,
+                quantity,
+            };
+            let mut child = ProductCycle::new_lot(
+                parent,
+                address,
+                farmer_profile.total_products,
+                parent.farmer,
+                clock.unix_timestamp,
+            );
+            record_history(
+                address,
+                &mut child,
+                HistoryEntry::Lot {
+                    parents: vec![origin.clone()],
+                    timestamp: clock.unix_timestamp,
+                },
+            );
+            child.parents = vec![origin];
+            child.actual_quantity = quantity;
+            child.estimated_quantity = quantity;
+            child.try_serialize(&mut &mut child_info.try_borrow_mut_data()?[..])?;
+            lots.push(address);
+
+            farmer_profile.total_products += 1;
+            farmer_profile.active_products += 1;
+        }
+
+        // The children pin the chain as it stood; the parent's ends on the split
+        record_history(
+            parent_key,
+            parent,
+            HistoryEntry::Split {
+                lots,
+                timestamp: clock.unix_timestamp,
+            },
+        );
+        // The stock now lives on the children
+        parent.lot_status = LotStatus::Split;
+        parent.available_stock = 0;
+        parent.updated_at = clock.unix_timestamp;
+        farmer_profile.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Pools harvested lots of one category, possibly from several farmers, into
+    // a new lot. Each parent is passed as a remaining account followed by its
+    // pre-order listing address, which may be empty, and its farmer, who must
+    // sign.
+    pub fn merge_batches<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MergeBatches<'info>>,
+    ) -> Result<()> {
+        let lot_key = ctx.accounts.lot.key();
+        let farmer_profile = &mut ctx.accounts.farmer_profile;
+        let clock = Clock::get()?;
+
+        let triples = ctx.remaining_accounts.chunks_exact(3);
+        require!(triples.remainder().is_empty(), ErrorCode::InvalidMerge);
+        require!(
+            (2..=MAX_MERGE_PARENTS).contains(&triples.len()),
+            ErrorCode::InvalidMerge
+        );
+
+        let mut parents: Vec<ProductCycle> = Vec::with_capacity(triples.len());
+        let mut origins: Vec<LotParent> = Vec::with_capacity(triples.len());
+        for triple in triples.clone() {
+            let (parent_info, listing_info, parent_farmer) = (&triple[0], &triple[1], &triple[2]);
+            require_keys_eq!(
+                *parent_info.owner,
+                crate::ID,
+                anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram
+            );
+            require!(
+                parent_info.is_writable,
+                anchor_lang::error::ErrorCode::ConstraintMut
+            );
+            require!(
+                origins.iter().all(|origin| origin.product != parent_info.key()),
+                ErrorCode::InvalidMerge
+            );
+            let parent = ProductCycle::try_deserialize(&mut &parent_info.try_borrow_data()?[..])?;
+            require_keys_eq!(parent.farmer, parent_farmer.key(), ErrorCode::UnauthorizedFarmer);
+            require!(parent_farmer.is_signer, ErrorCode::UnauthorizedFarmer);
+            let (listing_address, _) = Pubkey::find_program_address(
+                &[b"preorder", parent_info.key().as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(
+                listing_info.key(),
+                listing_address,
+                anchor_lang::error::ErrorCode::ConstraintSeeds
+            );
+            parent.check_divisible(read_optional_pda::<PreOrderListing>(listing_info)?.as_ref())?;
+            if let Some(first) = parents.first() {
+                require!(
+                    parent.category == first.category,
+                    ErrorCode::LotCategoryMismatch
+                );
+            }
+
+            origins.push(LotParent {
+                product: parent_info.key(),
+                history_hash: parent.history_hash,
+                quantity: parent.actual_quantity,
+            });
+            parents.push(parent);
+        }
+
+        let quantity = origins
+            .iter()
+            .try_fold(0u64, |sum, origin| sum.checked_add(origin.quantity))
+            .ok_or(ErrorCode::InvalidAmount)?;
+        let mut lot = ProductCycle::new_lot(
+            &parents[0],
+            lot_key,
+            farmer_profile.total_products,
+            ctx.accounts.farmer.key(),
+            clock.unix_timestamp,
+        );
+        lot.estimated_harvest_date = parents
+            .iter()
+            .map(|parent| parent.estimated_harvest_date)
+            .max()
+            .unwrap_or_default();
+        record_history(
+            lot_key,
+            &mut lot,
+            HistoryEntry::Lot {
+                parents: origins.clone(),
+                timestamp: clock.unix_timestamp,
+            },
+        );
+        lot.parents = origins;
+        lot.actual_quantity = quantity;
+        lot.estimated_quantity = quantity;
+        ctx.accounts.lot.set_inner(lot);
+
+        for (triple, mut parent) in triples.zip(parents) {
+            record_history(
+                triple[0].key(),
+                &mut parent,
+                HistoryEntry::Merged {
+                    lot: lot_key,
+                    timestamp: clock.unix_timestamp,
+                },
+            );
+            parent.lot_status = LotStatus::Merged;
+            parent.available_stock = 0;
+            parent.updated_at = clock.unix_timestamp;
+            parent.try_serialize(&mut &mut triple[0].try_borrow_mut_data()?[..])?;
+        }
+
+        farmer_profile.total_products += 1;
+        farmer_profile.active_products += 1;
+        farmer_profile.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn create_cooperative(ctx: Context<CreateCooperative>, name: String) -> Result<()> {
+        let cooperative = &mut ctx.accounts.cooperative;
+        let clock = Clock::get()?;
+
+        require!(
+            !name.is_empty() && name.len() <= MAX_COOP_NAME_LEN,
+            ErrorCode::InvalidCooperativeName
+        );
+
+        cooperative.admin = ctx.accounts.admin.key();
+        cooperative.name = name;
+        cooperative.members = Vec::new();
+        cooperative.created_at = clock.unix_timestamp;
+        cooperative.updated_at = clock.unix_timestamp;
+        cooperative.bump = ctx.bumps.cooperative;
+
+        Ok(())
+    }
+
+    // Signed by both the farmer and the coop admin, who approves the join
+    pub fn join_cooperative(ctx: Context<ManageMembership>) -> Result<()> {
+        let cooperative = &mut ctx.accounts.cooperative;
+        let farmer = ctx.accounts.farmer.key();
+        let clock = Clock::get()?;
+
+        require!(!cooperative.is_member(&farmer), ErrorCode::AlreadyMember);
+        require!(
+            cooperative.members.len() < MAX_COOP_MEMBERS,
+            ErrorCode::TooManyMembers
+        );
+
+        cooperative.members.push(CoopMember {
+            farmer,
+            profile: ctx.accounts.farmer_profile.key(),
+            joined_at: clock.unix_timestamp,
+            open_contributions: 0,
+        });
+        cooperative.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Also approved by the admin. Coop products the member supplied have to be
+    // distributed and closed first, so their proceeds are settled.
+    pub fn leave_cooperative(ctx: Context<ManageMembership>) -> Result<()> {
+        let cooperative = &mut ctx.accounts.cooperative;
+        let farmer = ctx.accounts.farmer.key();
+        let clock = Clock::get()?;
+
+        cooperative.remove_member(&farmer)?;
+        cooperative.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Lists which members supplied a coop product, and how much, so sale and
+    // campaign proceeds can be paid out in proportion.
+    pub fn register_coop_product(
+        ctx: Context<RegisterCoopProduct>,
+        contributions: Vec<MemberContribution>,
+    ) -> Result<()> {
+        let coop_product = &mut ctx.accounts.coop_product;
+        let cooperative = &mut ctx.accounts.cooperative;
+        let clock = Clock::get()?;
+
+        require!(
+            (1..=MAX_COOP_CONTRIBUTORS).contains(&contributions.len()),
+            ErrorCode::InvalidContributions
+        );
+        for (index, contribution) in contributions.iter().enumerate() {
+            require!(
+                cooperative.is_member(&contribution.farmer),
+                ErrorCode::NotAMember
+            );
+            require!(
+                contribution.quantity > 0
+                    && contributions[..index]
+                        .iter()
+                        .all(|earlier| earlier.farmer != contribution.farmer),
+                ErrorCode::InvalidContributions
+            );
+        }
+        cooperative.open_contributions(&contributions)?;
+        cooperative.updated_at = clock.unix_timestamp;
+
+        // Keep the vault rent-exempt so proceeds of any size can land in it
+        let ix = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.admin.to_account_info(),
+            to: ctx.accounts.coop_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), ix);
+        anchor_lang::system_program::transfer(cpi_ctx, Rent::get()?.minimum_balance(0))?;
+
+        coop_product.cooperative = cooperative.key();
+        coop_product.product = ctx.accounts.product_cycle.key();
+        coop_product.contributions = contributions;
+        coop_product.total_paid_out = 0;
+        coop_product.created_at = clock.unix_timestamp;
+        coop_product.updated_at = clock.unix_timestamp;
+        coop_product.bump = ctx.bumps.coop_product;
+        coop_product.vault_bump = ctx.bumps.coop_vault;
+
+        Ok(())
+    }
+
+    // Pays a coop product's proceeds into its vault. Campaigns can name the
+    // vault as a beneficiary instead, which pays it directly.
+    pub fn deposit_coop_proceeds(
+        ctx: Context<DepositCoopProceeds>,
+        amount: u64,
+        source: PayoutSource,
+    ) -> Result<()> {
+        let coop_product = &ctx.accounts.coop_product;
+        let clock = Clock::get()?;
+
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let ix = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.depositor.to_account_info(),
+            to: ctx.accounts.coop_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), ix);
+        anchor_lang::system_program::transfer(cpi_ctx, amount)?;
+
+        emit!(CoopProceedsDeposited {
+            cooperative: coop_product.cooperative,
+            product: coop_product.product,
+            depositor: ctx.accounts.depositor.key(),
+            source,
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Pays everything in the coop vault out to the contributors, in proportion
+    // to what they supplied. Their wallets are passed as remaining accounts in
+    // order.
+    pub fn distribute_coop_payout<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeCoopPayout<'info>>,
+    ) -> Result<()> {
+        let coop_product = &mut ctx.accounts.coop_product;
+        let clock = Clock::get()?;
+
+        require!(
+            ctx.remaining_accounts.len() == coop_product.contributions.len(),
+            ErrorCode::InvalidContributions
+        );
+        let amount = ctx
+            .accounts
+            .coop_vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let coop_key = coop_product.key();
+        let vault_seeds: &[&[u8]] = &[b"coop_vault", coop_key.as_ref(), &[coop_product.vault_bump]];
+        let payouts = coop_product.record_payout(amount)?;
+        for ((wallet, contribution), payout) in ctx
+            .remaining_accounts
+            .iter()
+            .zip(&coop_product.contributions)
+            .zip(payouts)
+        {
+            require_keys_eq!(wallet.key(), contribution.farmer, ErrorCode::NotAMember);
+            transfer_from_vault(
+                &ctx.accounts.coop_vault,
+                wallet,
+                &ctx.accounts.system_program,
+                &[vault_seeds],
+                payout,
+            )?;
+        }
+        coop_product.updated_at = clock.unix_timestamp;
+
+        emit!(CoopPayoutDistributed {
+            cooperative: coop_product.cooperative,
+            product: coop_product.product,
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// Passes for the product's farmer, or for an unexpired staff key holding
+// `permission` on the farmer's delegate account.
+fn require_farmer_or_staff(
+    farmer: &Pubkey,
+    signer: &Pubkey,
+    delegate: Option<&Delegate>,
+    permission: StaffPermission,
+    now: i64,
+) -> Result<()> {
+    if signer == farmer {
+        return Ok(());
+    }
+    let delegate = delegate.ok_or(ErrorCode::UnauthorizedFarmer)?;
+    require_keys_eq!(delegate.farmer, *farmer, ErrorCode::UnauthorizedFarmer);
+    require!(
+        delegate.allows(signer, permission, now),
+        ErrorCode::UnauthorizedStaff
+    );
+
+    Ok(())
+}
+
+// Extends the product's history chain and logs the entry, so the chain can be
+// replayed from transaction logs even for entries the account does not keep.
+fn record_history(product: Pubkey, product_cycle: &mut ProductCycle, entry: HistoryEntry) {
+    product_cycle.history_hash = entry.chain(&product_cycle.history_hash);
+    emit!(HistoryRecorded {
+        product,
+        entry,
+        history_hash: product_cycle.history_hash,
+    });
+}
+
+// Reads a PDA the context has already pinned by seeds but which may not exist yet.
+fn read_optional_pda<T: AccountDeserialize + Owner>(info: &AccountInfo) -> Result<Option<T>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    require_keys_eq!(
+        *info.owner,
+        T::owner(),
+        anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram
+    );
+
+    Ok(Some(T::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+}
+
+// Same as Anchor's `init` constraint, for PDAs the context cannot type.
+fn create_program_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let rent = Rent::get()?;
+    if info.lamports() == 0 {
+        let ix = anchor_lang::system_program::CreateAccount {
+            from: payer.to_account_info(),
+            to: info.clone(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(system_program.to_account_info(), ix, signer_seeds);
+        return anchor_lang::system_program::create_account(
+            cpi_ctx,
+            rent.minimum_balance(space),
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    // Anyone can send lamports to the address first, and CreateAccount refuses
+    // an account that has some, so top it up and allocate it in place.
+    let shortfall = rent_shortfall(&rent, info.lamports(), space);
+    if shortfall > 0 {
+        let ix = anchor_lang::system_program::Transfer {
+            from: payer.to_account_info(),
+            to: info.clone(),
+        };
+        let cpi_ctx = CpiContext::new(system_program.to_account_info(), ix);
+        anchor_lang::system_program::transfer(cpi_ctx, shortfall)?;
+    }
+    let ix = anchor_lang::system_program::Allocate {
+        account_to_allocate: info.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(system_program.to_account_info(), ix, signer_seeds);
+    anchor_lang::system_program::allocate(cpi_ctx, space as u64)?;
+    let ix = anchor_lang::system_program::Assign {
+        account_to_assign: info.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(system_program.to_account_info(), ix, signer_seeds);
+    anchor_lang::system_program::assign(cpi_ctx, &crate::ID)
+}
+
+// Lamports an account holding `lamports` still needs to be rent-exempt at `space` bytes
+pub fn rent_shortfall(rent: &Rent, lamports: u64, space: usize) -> u64 {
+    rent.minimum_balance(space).saturating_sub(lamports)
+}
+
+// Same as Anchor's `close` constraint, for accounts the context cannot type.
+fn close_program_account<'info>(
+    info: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    **destination.try_borrow_mut_lamports()? += info.lamports();
+    **info.try_borrow_mut_lamports()? = 0;
+    info.assign(&anchor_lang::system_program::ID);
+    info.resize(0)?;
+
+    Ok(())
+}
+
+// NUTS codes (country prefix plus up to three levels, e.g. "PL911") or TERYT
+// codes (voivodeship, powiat or gmina, e.g. "1465011").
+pub fn is_valid_region_code(code: &str) -> bool {
+    let bytes = code.as_bytes();
+    let is_nuts = (2..=5).contains(&bytes.len())
+        && bytes[..2].iter().all(u8::is_ascii_uppercase)
+        && bytes[2..]
+            .iter()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit());
+    let is_teryt =
+        [2, 4, 6, 7].contains(&bytes.len()) && bytes.iter().all(u8::is_ascii_digit);
+
+    is_nuts || is_teryt
+}
+
+pub fn is_valid_geohash(geohash: &str, max_len: usize) -> bool {
+    const ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+    (1..=max_len).contains(&geohash.len())
+        && geohash.bytes().all(|b| ALPHABET.contains(&b))
+}
+
+// Empty means no private data; anything else must be a sealed envelope
+pub fn is_valid_encrypted_data(encrypted_data: &str) -> bool {
+    encrypted_data.is_empty() || envelope::is_valid_profile_data(encrypted_data)
+}
+
+pub fn is_valid_media(media: &[MediaRef]) -> bool {
+    media.len() <= MAX_MEDIA_PER_ENTRY
+        && media.iter().all(|item| {
+            item.uri.len() <= MAX_MEDIA_URI_LEN
+                && MEDIA_URI_PREFIXES
+                    .iter()
+                    .any(|prefix| item.uri.len() > prefix.len() && item.uri.starts_with(prefix))
+                && MEDIA_MIME_TYPES.contains(&item.mime.as_str())
+                && item.sha256 != [0; 32]
+        })
+}
+
+// Product addresses come from the farmer and a timestamp, so a closed product
+// can be recreated at the same address. The farmer's `total_products` only
+// ever grows, which makes the id, and the history chain seeded from it,
+// different for every creation.
+pub fn new_product_id(address: &Pubkey, serial: u64) -> String {
+    hashv(&[address.as_ref(), &serial.to_le_bytes()]).to_string()
+}
+
+pub fn history_genesis(product_id: &str) -> [u8; 32] {
+    hashv(&[b"history", product_id.as_bytes()]).to_bytes()
+}
+
+// Stretch goals lie above the goal in ascending order and within the hard cap,
+// and only make sense for campaigns that keep going past the goal.
+pub fn is_valid_funding(goal_amount: u64, funding: &FundingOptions) -> bool {
+    let goals = &funding.stretch_goals;
+    let ceiling = funding.hard_cap.unwrap_or(u64::MAX);
+
+    (funding.allow_overfunding || (funding.hard_cap.is_none() && goals.is_empty()))
+        && ceiling >= goal_amount
+        && goals.len() <= MAX_STRETCH_GOALS
+        && goals.iter().all(|goal| {
+            goal.amount > goal_amount
+                && goal.amount <= ceiling
+                && goal.milestone.len() <= MAX_STRETCH_GOAL_MILESTONE_LEN
+        })
+        && goals.windows(2).all(|pair| pair[0].amount < pair[1].amount)
+}
+
+// Shares are positive, go to distinct wallets and add up to the whole vault
+pub fn is_valid_beneficiaries(beneficiaries: &[Beneficiary]) -> bool {
+    (1..=MAX_CAMPAIGN_BENEFICIARIES).contains(&beneficiaries.len())
+        && beneficiaries.iter().all(|beneficiary| beneficiary.share_bps > 0)
+        && beneficiaries.iter().enumerate().all(|(index, beneficiary)| {
+            beneficiaries[..index]
+                .iter()
+                .all(|earlier| earlier.wallet != beneficiary.wallet)
+        })
+        && beneficiaries
+            .iter()
+            .map(|beneficiary| beneficiary.share_bps as u64)
+            .sum::<u64>()
+            == BPS_DENOMINATOR
+}
+
+// Every child lot gets a share, and together they account for the whole harvest
+pub fn is_valid_split(quantities: &[u64], actual_quantity: u64) -> bool {
+    (2..=MAX_SPLIT_CHILDREN).contains(&quantities.len())
+        && quantities.iter().all(|quantity| *quantity > 0)
+        && quantities
+            .iter()
+            .try_fold(0u64, |sum, quantity| sum.checked_add(*quantity))
+            == Some(actual_quantity)
+}
+
+pub fn is_valid_fee_bps(fee_bps: u16) -> bool {
+    fee_bps <= MAX_PLATFORM_FEE_BPS
+}
+
+// Splits `amount` into the platform's fee and what is left for the farmer. The
+// fee rounds down, so dust always stays with the farmer.
+pub fn split_platform_fee(amount: u64, fee_bps: u16) -> (u64, u64) {
+    let fee = (amount as u128 * fee_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+    (fee, amount - fee)
+}
+
+// Pays `amount` out of a vault to the farmer, routing the platform's cut to the
+// treasury. Returns the fee taken.
+fn pay_farmer_from_vault<'info>(
+    vault: &AccountInfo<'info>,
+    farmer: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+    fee_bps: u16,
+) -> Result<u64> {
+    let (fee, net) = split_platform_fee(amount, fee_bps);
+    transfer_from_vault(vault, treasury, system_program, signer_seeds, fee)?;
+    transfer_from_vault(vault, farmer, system_program, signer_seeds, net)?;
+
+    Ok(fee)
+}
+
+fn transfer_from_vault<'info>(
+    vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let ix = anchor_lang::system_program::Transfer {
+        from: vault.clone(),
+        to: to.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(system_program.to_account_info(), ix, signer_seeds);
+    anchor_lang::system_program::transfer(cpi_ctx, amount)
+}
+
+// Data structures
+#[account]
+pub struct FarmerProfile {
+    pub version: u8,
+    pub farmer: Pubkey,                    // Changed from farmer_wallet to farmer
+    pub encrypted_data: String,
+    pub public_name: String,
+    pub region: String,
+    pub certifications: Vec<String>,
+    pub verification_status: bool,
+    pub reputation_score: u64,
+    pub total_products: u64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub active_products: u64,            // Products not yet closed
+    pub active_campaigns: u64,           // Campaigns not yet closed
+    pub region_code: String,             // NUTS or TERYT code
+    pub geohash: Option<String>,         // Coarse farm location
+    pub anonymised: bool,                // Personal fields erased for good
+    pub active_grants: u32,              // Access grants not yet revoked
+}
+
+#[account]
+pub struct ProductCycle {
+    pub version: u8,
+    pub product_id: String,
+    pub farmer: Pubkey,
+    pub product_name: String,
+    pub category: ProductCategory,
+    pub subcategory: Option<ProductSubcategory>,
+    pub description: String,
+    pub estimated_harvest_date: i64,
+    pub estimated_quantity: u64,
+    pub actual_quantity: u64,
+    pub firebase_image_urls: Vec<String>, // Unhashed, only on migrated products
+    pub growth_updates: Vec<GrowthUpdate>,
+    pub delivery_updates: Vec<DeliveryUpdate>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub price_per_unit: u64,             // In lamports, or base units of `price_mint`
+    pub price_mint: Option<Pubkey>,      // None = priced in SOL
+    pub unit: UnitOfMeasure,
+    pub available_stock: u64,            // In `unit`s
+    pub min_order_quantity: u64,
+    pub media: Vec<MediaRef>,
+    pub history_hash: [u8; 32],          // Head of the hash chain over the logs
+    pub parents: Vec<LotParent>,         // Lots this one was split or merged from
+    pub lot_status: LotStatus,
+    pub share_campaigns: u8,             // Campaigns whose harvest shares redeem against it
+    pub active_subscriptions: u32,       // Subscriptions with instalments still to deliver
+    pub rent_payer: Pubkey,              // Farmer or staff key; refunded on close
+}
+
+#[account]
+pub struct CrowdfundingCampaign {
+    pub version: u8,
+    pub campaign_id: String,
+    pub farmer: Pubkey,
+    pub title: String,
+    pub description: String,
+    pub goal_amount: u64,
+    pub current_amount: u64,
+    pub deadline: i64,
+    pub campaign_type: CampaignType,
+    pub milestones: Vec<String>,
+    pub contributors: Vec<Contributor>,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub is_cancelled: bool,
+    pub deadline_extended: bool,
+    pub total_withdrawn: u64,
+    pub beneficiaries: Vec<Beneficiary>, // Share the withdrawn funds
+    pub allow_overfunding: bool,         // Keep taking contributions past the goal
+    pub hard_cap: Option<u64>,           // Closes the campaign when reached
+    pub stretch_goals: Vec<StretchGoal>, // Ascending, above the goal
+    pub stretch_goals_reached: u8,
+}
+
+impl FarmerProfile {
+    pub const SPACE: usize = 8 + 1 + 32 + 256 + 128 + 64 + 512 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 16 + 16 + 1 + 4;
+
+    // Wipes the personal fields once every access grant is gone; a buyer
+    // holding a grant could otherwise still read the old data key.
+    pub fn anonymise(&mut self, now: i64) -> Result<()> {
+        require!(self.active_grants == 0, ErrorCode::AccessGrantsOutstanding);
+
+        self.encrypted_data = String::new();
+        self.public_name = String::new();
+        self.region = String::new();
+        self.region_code = String::new();
+        self.geohash = None;
+        self.anonymised = true;
+        self.updated_at = now;
+
+        Ok(())
+    }
+}
+
+impl ProductCycle {
+    pub const SPACE: usize = 8 + 1 + 64 + 32 + 128 + 1 + 2 + 256 + 8 + 8 + 8 + 512 + 2048 + 2048 + 8 + 8 + 8 + 33 + 1 + 8 + 8 + 1024 + 32 + 4 + MAX_MERGE_PARENTS * (32 + 32 + 8) + 1 + 1 + 4 + 32;
+}
+
+impl ProductCycle {
+    // An unpriced lot that inherits what it is from `template`; the caller
+    // sets the quantities and parent links.
+    pub fn new_lot(
+        template: &ProductCycle,
+        address: Pubkey,
+        serial: u64,
+        farmer: Pubkey,
+        now: i64,
+    ) -> Self {
+        let product_id = new_product_id(&address, serial);
+        Self {
+            version: PRODUCT_CYCLE_VERSION,
+            history_hash: history_genesis(&product_id),
+            product_id,
+            farmer,
+            product_name: template.product_name.clone(),
+            category: template.category,
+            subcategory: template.subcategory,
+            description: template.description.clone(),
+            estimated_harvest_date: template.estimated_harvest_date,
+            estimated_quantity: 0,
+            actual_quantity: 0,
+            firebase_image_urls: Vec::new(),
+            growth_updates: Vec::new(),
+            delivery_updates: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            price_per_unit: 0,
+            price_mint: None,
+            unit: template.unit,
+            available_stock: 0,
+            min_order_quantity: 1,
+            media: template.media.clone(),
+            parents: Vec::new(),
+            lot_status: LotStatus::Active,
+            share_campaigns: 0,
+            active_subscriptions: 0,
+            rent_payer: farmer,
+        }
+    }
+
+    pub fn set_pricing(
+        &mut self,
+        price_per_unit: u64,
+        price_mint: Option<Pubkey>,
+        unit: UnitOfMeasure,
+        min_order_quantity: u64,
+    ) -> Result<()> {
+        require!(price_per_unit > 0, ErrorCode::InvalidPrice);
+        require!(min_order_quantity > 0, ErrorCode::InvalidMinOrderQuantity);
+
+        self.price_per_unit = price_per_unit;
+        self.price_mint = price_mint;
+        self.unit = unit;
+        self.min_order_quantity = min_order_quantity;
+
+        Ok(())
+    }
+
+    // Stock never goes below zero; a larger withdrawal fails and changes nothing
+    pub fn adjust_stock(&mut self, delta: i64) -> Result<()> {
+        self.available_stock = self
+            .available_stock
+            .checked_add_signed(delta)
+            .ok_or(ErrorCode::InsufficientStock)?;
+
+        Ok(())
+    }
+
+    // Additional fields for the batch certificate. They point back at the
+    // product PDA, where the full history lives.
+    pub fn certificate_metadata(&self, address: &Pubkey) -> Vec<(String, String)> {
+        vec![
+            ("product".to_string(), address.to_string()),
+            ("farmer".to_string(), self.farmer.to_string()),
+            ("quantity".to_string(), self.actual_quantity.to_string()),
+            (
+                "stages".to_string(),
+                self.growth_updates
+                    .iter()
+                    .map(|u| format!("{:?}:{}", u.stage, u.timestamp))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            (
+                "deliveries".to_string(),
+                self.delivery_updates
+                    .iter()
+                    .map(|u| format!("{:?}:{}", u.status, u.timestamp))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+        ]
+    }
+
+    // Fails while anything that reads the product is still open. A
+    // certificate's metadata and harvest shares point at it for good; buyers
+    // need a pre-order listing until every reservation is settled.
+    pub fn check_closable(
+        &self,
+        has_certificate: bool,
+        has_courier: bool,
+        pre_order: Option<&PreOrderListing>,
+    ) -> Result<()> {
+        require!(!has_certificate, ErrorCode::ProductHasCertificate);
+        require!(self.share_campaigns == 0, ErrorCode::ProductHasHarvestShares);
+        require!(!has_courier, ErrorCode::ProductHasCourier);
+        require!(
+            self.active_subscriptions == 0,
+            ErrorCode::ProductHasSubscriptions
+        );
+        if let Some(listing) = pre_order {
+            require!(listing.open_reservations == 0, ErrorCode::PreOrderUnsettled);
+        }
+
+        Ok(())
+    }
+
+    // Splitting or merging moves the stock to new lots, so nothing may still be
+    // owed against this one
+    pub fn check_divisible(&self, pre_order: Option<&PreOrderListing>) -> Result<()> {
+        require!(self.lot_status == LotStatus::Active, ErrorCode::LotNotActive);
+        require!(self.actual_quantity > 0, ErrorCode::HarvestNotRecorded);
+        require!(self.share_campaigns == 0, ErrorCode::ProductHasHarvestShares);
+        require!(
+            self.active_subscriptions == 0,
+            ErrorCode::ProductHasSubscriptions
+        );
+        if let Some(listing) = pre_order {
+            require!(listing.open_reservations == 0, ErrorCode::PreOrderUnsettled);
+        }
+
+        Ok(())
+    }
+
+    // Whether the last delivery was confirmed but not yet completed. Only the
+    // courier or the recipient can post `Delivered`, so this is what lets the
+    // farmer close a delivery.
+    pub fn awaiting_completion(&self) -> bool {
+        self.delivery_updates
+            .iter()
+            .rev()
+            .find_map(|update| match update.status {
+                DeliveryStatus::Delivered => Some(true),
+                DeliveryStatus::Completed => Some(false),
+                _ => None,
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl Cooperative {
+    pub const SPACE: usize =
+        8 + 32 + 4 + MAX_COOP_NAME_LEN + 4 + MAX_COOP_MEMBERS * (32 + 32 + 8 + 2) + 8 + 8 + 1;
+
+    pub fn is_member(&self, farmer: &Pubkey) -> bool {
+        self.members.iter().any(|member| member.farmer == *farmer)
+    }
+
+    // Members stay until every coop product they supplied has been closed,
+    // which only happens once its proceeds are distributed
+    pub fn open_contributions(&mut self, contributions: &[MemberContribution]) -> Result<()> {
+        for contribution in contributions {
+            let member = self
+                .members
+                .iter_mut()
+                .find(|member| member.farmer == contribution.farmer)
+                .ok_or(ErrorCode::NotAMember)?;
+            member.open_contributions = member
+                .open_contributions
+                .checked_add(1)
+                .ok_or(ErrorCode::InvalidContributions)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn close_contributions(&mut self, contributions: &[MemberContribution]) {
+        for member in self.members.iter_mut() {
+            if contributions
+                .iter()
+                .any(|contribution| contribution.farmer == member.farmer)
+            {
+                member.open_contributions = member.open_contributions.saturating_sub(1);
+            }
+        }
+    }
+
+    pub fn remove_member(&mut self, farmer: &Pubkey) -> Result<()> {
+        let index = self
+            .members
+            .iter()
+            .position(|member| member.farmer == *farmer)
+            .ok_or(ErrorCode::NotAMember)?;
+        require!(
+            self.members[index].open_contributions == 0,
+            ErrorCode::CoopContributionsOpen
+        );
+        self.members.remove(index);
+
+        Ok(())
+    }
+}
+
+impl CoopProduct {
+    pub const SPACE: usize =
+        8 + 32 + 32 + 4 + MAX_COOP_CONTRIBUTORS * (32 + 8) + 8 + 8 + 8 + 1 + 1;
+
+    // Splits `amount` in proportion to the quantities supplied. Rounding dust
+    // goes to the first contributor, so the payouts always sum to `amount`.
+    pub fn payouts(&self, amount: u64) -> Vec<u64> {
+        let total: u128 = self
+            .contributions
+            .iter()
+            .map(|contribution| contribution.quantity as u128)
+            .sum();
+        if total == 0 {
+            return vec![0; self.contributions.len()];
+        }
+
+        let mut payouts: Vec<u64> = self
+            .contributions
+            .iter()
+            .map(|contribution| (amount as u128 * contribution.quantity as u128 / total) as u64)
+            .collect();
+        let dust = amount - payouts.iter().sum::<u64>();
+        if let Some(first) = payouts.first_mut() {
+            *first += dust;
+        }
+        payouts
+    }
+
+    pub fn record_payout(&mut self, amount: u64) -> Result<Vec<u64>> {
+        self.total_paid_out = self
+            .total_paid_out
+            .checked_add(amount)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        Ok(self.payouts(amount))
+    }
+}
+
+impl CrowdfundingCampaign {
+    pub const SPACE: usize = 8 + 1 + 64 + 32 + 128 + 256 + 8 + 8 + 8 + 64 + 512 + 2048 + 1 + 8 + 8 + 1 + 1 + 8 + 4 + MAX_CAMPAIGN_BENEFICIARIES * (32 + 2) + 1 + 9 + 4 + MAX_STRETCH_GOALS * (8 + 4 + MAX_STRETCH_GOAL_MILESTONE_LEN) + 1;
+
+    // What `contributor` is owed back. Cancelled campaigns and ones that missed
+    // their goal by the deadline are refundable, except to backers who already
+    // hold harvest shares for their contribution.
+    pub fn refund_for(&self, contributor: &Pubkey, now: i64, claimed_shares: bool) -> Result<u64> {
+        let failed = now > self.deadline && self.current_amount < self.goal_amount;
+        require!(self.is_cancelled || failed, ErrorCode::CampaignNotRefundable);
+        require!(!claimed_shares, ErrorCode::SharesAlreadyClaimed);
+
+        let refund = self
+            .contributors
+            .iter()
+            .filter(|c| c.wallet == *contributor)
+            .map(|c| c.amount)
+            .sum::<u64>();
+        require!(refund > 0, ErrorCode::NotAContributor);
+
+        Ok(refund)
+    }
+
+    // Refunds are paid from the vault, so nothing may have left it yet, and
+    // backers holding shares could never be refunded.
+    pub fn check_cancellable(&self, harvest_shares: Option<&HarvestShares>) -> Result<()> {
+        require!(!self.is_cancelled, ErrorCode::CampaignCancelled);
+        require!(self.total_withdrawn == 0, ErrorCode::CampaignFundsWithdrawn);
+        require!(
+            harvest_shares.is_none_or(|shares| shares.total_shares == 0),
+            ErrorCode::SharesAlreadyClaimed
+        );
+
+        Ok(())
+    }
+
+    // Taking contributions: neither closed nor past the deadline
+    pub fn is_open(&self, now: i64) -> bool {
+        self.is_active && now <= self.deadline
+    }
+
+    // Adds `amount` from `wallet`, all or nothing: a contribution that would
+    // pass the hard cap is refused whole. Returns the stretch goals it newly
+    // reached, so each is announced once.
+    pub fn record_contribution(
+        &mut self,
+        wallet: Pubkey,
+        amount: u64,
+        now: i64,
+    ) -> Result<std::ops::Range<u8>> {
+        let current_amount = self
+            .current_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        if let Some(hard_cap) = self.hard_cap {
+            require!(current_amount <= hard_cap, ErrorCode::ExceedsHardCap);
+        }
+
+        // Repeat donors keep one entry, so small recurring gifts do not fill the account
+        match self.contributors.iter_mut().find(|c| c.wallet == wallet) {
+            Some(contributor) => {
+                contributor.amount += amount;
+                contributor.timestamp = now;
+            }
+            None => self.contributors.push(Contributor {
+                wallet,
+                amount,
+                timestamp: now,
+            }),
+        }
+        self.current_amount = current_amount;
+        self.updated_at = now;
+
+        let reached = self.stretch_goals_reached_by(current_amount);
+        let newly_reached = self.stretch_goals_reached..reached.max(self.stretch_goals_reached);
+        self.stretch_goals_reached = newly_reached.end;
+
+        // Without overfunding the campaign closes at its goal; with it, at the cap
+        let limit = if self.allow_overfunding {
+            self.hard_cap
+        } else {
+            Some(self.goal_amount)
+        };
+        if limit.is_some_and(|limit| current_amount >= limit) {
+            self.is_active = false;
+        }
+
+        Ok(newly_reached)
+    }
+
+    // Stretch goals are ascending, so this is how many of them `amount` covers
+    pub fn stretch_goals_reached_by(&self, amount: u64) -> u8 {
+        self.stretch_goals
+            .iter()
+            .take_while(|goal| amount >= goal.amount)
+            .count() as u8
+    }
+
+    // Splits `amount` by the beneficiaries' shares. Rounding dust goes to the
+    // first beneficiary, so the payouts always sum to `amount`.
+    pub fn payouts(&self, amount: u64) -> Vec<u64> {
+        let mut payouts: Vec<u64> = self
+            .beneficiaries
+            .iter()
+            .map(|beneficiary| {
+                (amount as u128 * beneficiary.share_bps as u128 / BPS_DENOMINATOR as u128) as u64
+            })
+            .collect();
+        let dust = amount - payouts.iter().sum::<u64>();
+        if let Some(first) = payouts.first_mut() {
+            *first += dust;
+        }
+        payouts
+    }
+}
+
+#[account]
+pub struct PreOrderListing {
+    pub product: Pubkey,
+    pub farmer: Pubkey,
+    pub price_per_unit: u64,             // w lamportach (SOL)
+    pub reservable_quantity: u64,
+    pub reserved_quantity: u64,
+    pub harvested_quantity: Option<u64>, // Set by the first update_actual_quantity
+    pub estimated_harvest_date: i64,
+    pub is_open: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub bump: u8,
+    pub vault_bump: u8,
+    pub open_reservations: u32,          // Reservations not yet settled
+}
+
+#[account]
+pub struct Reservation {
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub quantity: u64,
+    pub amount_paid: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct Subscription {
+    pub farmer: Pubkey,
+    pub buyer: Pubkey,
+    pub product_cycle: Pubkey,           // Deliveries are logged on this product
+    pub price_per_delivery: u64,         // w lamportach (SOL)
+    pub total_deliveries: u32,
+    pub delivered_count: u32,            // Instalments paid out
+    pub confirmed_count: u32,            // Deliveries confirmed by the buyer or courier
+    pub is_active: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+#[account]
+pub struct HarvestShares {
+    pub campaign: Pubkey,
+    pub product_cycle: Pubkey,
+    pub share_mint: Pubkey,
+    pub total_shares: u64,               // Fixed at the first claim
+    pub redeemed_shares: u64,
+    pub redeemed_quantity: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct ShareClaim {
+    pub campaign: Pubkey,
+    pub contributor: Pubkey,
+    pub shares: u64,
+    pub claimed_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct PlatformConfig {
+    pub admin: Pubkey,
+    pub guardian: Pubkey,                // May flip pause flags alongside the admin
+    pub campaign_fee_bps: u16,
+    pub order_fee_bps: u16,
+    pub paused: bool,                    // Stops every gated instruction
+    pub crowdfunding_paused: bool,
+    pub orders_paused: bool,
+    pub profile_writes_paused: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub bump: u8,
+    pub treasury_bump: u8,
+}
+
+#[account]
+pub struct Cooperative {
+    pub admin: Pubkey,
+    pub name: String,
+    pub members: Vec<CoopMember>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct CoopProduct {
+    pub cooperative: Pubkey,
+    pub product: Pubkey,                 // Listed under the coop admin's wallet
+    pub contributions: Vec<MemberContribution>,
+    pub total_paid_out: u64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub bump: u8,
+    pub vault_bump: u8,                  // Proceeds wait here until distributed
+}
+
+// Left behind by close_farmer_profile so a recreated profile keeps its history
+#[account]
+pub struct FarmerTombstone {
+    pub farmer: Pubkey,
+    pub reputation_score: u64,
+    pub total_products: u64,
+    pub closed_at: i64,
+    pub bump: u8,
+}
+
+// A farmer's profile data key, wrapped for one buyer
+#[account]
+pub struct AccessGrant {
+    pub farmer: Pubkey,
+    pub buyer: Pubkey,
+    pub buyer_encryption_key: [u8; 32], // X25519 key the data key is wrapped to
+    pub wrapped_key: Vec<u8>,           // See `envelope`
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+// The courier carrying a product, and who may confirm it arrived
+#[account]
+pub struct CourierAssignment {
+    pub product: Pubkey,
+    pub farmer: Pubkey,
+    pub courier: Pubkey,
+    pub recipient: Option<Pubkey>,       // Buyer who may confirm delivery
+    pub assigned_at: i64,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+// Staff keys a farmer has authorised to act on their products
+#[account]
+pub struct Delegate {
+    pub farmer: Pubkey,
+    pub staff: Vec<StaffKey>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl PreOrderListing {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 9 + 8 + 1 + 8 + 8 + 1 + 1 + 4;
+
+    // Splits what the buyer paid into the farmer's payment and the buyer's refund.
+    // Reservations are filled pro rata when the harvest falls short of the book,
+    // rounding the filled quantity down in the buyer's favour.
+    pub fn settlement(&self, reservation: &Reservation, now: i64) -> Result<(u64, u64)> {
+        let filled_quantity = match self.harvested_quantity {
+            Some(harvested) => {
+                let fulfilled = harvested.min(self.reserved_quantity);
+                (reservation.quantity as u128 * fulfilled as u128
+                    / self.reserved_quantity as u128) as u64
+            }
+            None => {
+                require!(
+                    now > self.estimated_harvest_date + PRE_ORDER_REFUND_GRACE_PERIOD,
+                    ErrorCode::HarvestNotRecorded
+                );
+                0
+            }
+        };
+        let payment = filled_quantity
+            .checked_mul(self.price_per_unit)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        let refund = reservation
+            .amount_paid
+            .checked_sub(payment)
+            .ok_or(ErrorCode::InvalidAmount)?;
+
+        Ok((payment, refund))
+    }
+}
+
+impl Reservation {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+impl Subscription {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 4 + 4 + 4 + 1 + 8 + 8 + 1 + 1;
+
+    pub fn vault_address(&self, subscription: &Pubkey) -> Option<Pubkey> {
+        Pubkey::create_program_address(
+            &[b"subscription_vault", subscription.as_ref(), &[self.vault_bump]],
+            &crate::ID,
+        )
+        .ok()
+    }
+
+    // Marks the current delivery confirmed; confirming it again changes nothing
+    pub fn confirm_delivery(&mut self) {
+        if self.is_active {
+            self.confirmed_count = self.delivered_count + 1;
+        }
+    }
+
+    // Pays out the confirmed delivery, once; false if there is none to pay
+    pub fn release_instalment(&mut self) -> bool {
+        if !self.is_active || self.confirmed_count <= self.delivered_count {
+            return false;
+        }
+        self.delivered_count += 1;
+        self.is_active = self.delivered_count < self.total_deliveries;
+        true
+    }
+
+    // Undelivered instalments plus the rent reserve go back to the buyer. A
+    // confirmed delivery belongs to the farmer, so it has to be released first.
+    pub fn cancellation_refund(&self, rent_reserve: u64) -> Result<u64> {
+        require!(
+            self.confirmed_count <= self.delivered_count,
+            ErrorCode::InstalmentAwaitingRelease
+        );
+        let undelivered = self.total_deliveries.saturating_sub(self.delivered_count) as u64;
+
+        undelivered
+            .checked_mul(self.price_per_delivery)
+            .and_then(|amount| amount.checked_add(rent_reserve))
+            .ok_or(error!(ErrorCode::InvalidAmount))
+    }
+}
+
+impl HarvestShares {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 1;
+
+    // Refunds lower the campaign's `current_amount` after shares are minted,
+    // so the supply is taken from it once, when the first backer claims.
+    pub fn snapshot_total(&mut self, current_amount: u64) {
+        if self.total_shares == 0 {
+            self.total_shares = current_amount;
+        }
+    }
+
+    // The part of `harvest` that `shares` redeem for, rounded down
+    pub fn quantity_for(&self, shares: u64, harvest: u64) -> u64 {
+        if self.total_shares == 0 {
+            return 0;
+        }
+        (shares as u128 * harvest as u128 / self.total_shares as u128) as u64
+    }
+}
+
+impl ShareClaim {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+impl FarmerTombstone {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 8 + 1;
+}
+
+impl AccessGrant {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 4 + envelope::WRAPPED_KEY_LEN + 8 + 1;
+}
+
+impl CourierAssignment {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 33 + 8 + 8 + 1;
+}
+
+impl Delegate {
+    pub const SPACE: usize = 8 + 32 + 4 + MAX_STAFF_KEYS * (32 + 3 + 8) + 8 + 8 + 1;
+
+    pub fn allows(&self, key: &Pubkey, permission: StaffPermission, now: i64) -> bool {
+        self.staff.iter().any(|staff| {
+            staff.key == *key
+                && staff.expires_at > now
+                && match permission {
+                    StaffPermission::GrowthUpdates => staff.permissions.growth_updates,
+                    StaffPermission::DeliveryUpdates => staff.permissions.delivery_updates,
+                    StaffPermission::ProductCreation => staff.permissions.product_creation,
+                }
+        })
+    }
+}
+
+impl PlatformConfig {
+    pub const SPACE: usize = 8 + 32 + 32 + 2 + 2 + 1 + 1 + 1 + 1 + 8 + 8 + 1 + 1;
+
+    // Withdrawals, refunds and settlements are deliberately never gated on this
+    pub fn is_paused(&self, feature: PausableFeature) -> bool {
+        self.paused
+            || match feature {
+                PausableFeature::Crowdfunding => self.crowdfunding_paused,
+                PausableFeature::Orders => self.orders_paused,
+                PausableFeature::ProfileWrites => self.profile_writes_paused,
+            }
+    }
+
+    pub fn can_pause(&self, key: &Pubkey) -> bool {
+        *key == self.admin || *key == self.guardian
+    }
+
+    // `None` flips the global flag, which overrides every per-feature flag
+    pub fn set_pause_flag(&mut self, feature: Option<PausableFeature>, paused: bool) {
+        match feature {
+            None => self.paused = paused,
+            Some(PausableFeature::Crowdfunding) => self.crowdfunding_paused = paused,
+            Some(PausableFeature::Orders) => self.orders_paused = paused,
+            Some(PausableFeature::ProfileWrites) => self.profile_writes_paused = paused,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct StaffKey {
+    pub key: Pubkey,
+    pub permissions: StaffPermissions,
+    pub expires_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct StaffPermissions {
+    pub growth_updates: bool,
+    pub delivery_updates: bool,
+    pub product_creation: bool,
+}
+
+// Digest returned by verify_provenance. `history_hash` is the head of the
+// product's history chain, of which only the most recent entries are listed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ProvenanceProof {
+    pub product: Pubkey,
+    pub farmer: Pubkey,
+    pub farmer_verified: bool,
+    pub certifications: Vec<String>,
+    pub growth_timeline: Vec<StageMark>,
+    pub delivery_chain: Vec<CustodyMark>,
+    pub history_hash: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct StageMark {
+    pub stage: GrowthStage,
+    pub reached_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct CustodyMark {
+    pub status: DeliveryStatus,
+    pub timestamp: i64,
+    pub signed_by: Pubkey,
+}
+
+impl ProvenanceProof {
+    pub fn new(product: Pubkey, product_cycle: &ProductCycle, profile: &FarmerProfile) -> Self {
+        // Only stage changes matter to a buyer, not every photo of the same stage
+        let mut growth_timeline: Vec<StageMark> = Vec::new();
+        for update in &product_cycle.growth_updates {
+            if growth_timeline.last().map(|mark| mark.stage) != Some(update.stage) {
+                growth_timeline.push(StageMark {
+                    stage: update.stage,
+                    reached_at: update.timestamp,
+                });
+            }
+        }
+        let skip = growth_timeline.len().saturating_sub(MAX_PROOF_GROWTH_STAGES);
+        growth_timeline.drain(..skip);
+
+        let skip = product_cycle
+            .delivery_updates
+            .len()
+            .saturating_sub(MAX_PROOF_DELIVERY_STEPS);
+        let delivery_chain = product_cycle.delivery_updates[skip..]
+            .iter()
+            .map(|update| CustodyMark {
+                status: update.status,
+                timestamp: update.timestamp,
+                signed_by: update.signed_by,
+            })
+            .collect();
+
+        Self {
+            product,
+            farmer: product_cycle.farmer,
+            farmer_verified: profile.verification_status,
+            certifications: profile
+                .certifications
+                .iter()
+                .take(MAX_PROOF_CERTIFICATIONS)
+                .cloned()
+                .collect(),
+            growth_timeline,
+            delivery_chain,
+            history_hash: product_cycle.history_hash,
+        }
+    }
+}
+
+// One link of a product's history chain: `hash(prev || borsh(entry))`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum HistoryEntry {
+    Growth(GrowthUpdate),
+    Delivery(DeliveryUpdate),
+    ActualQuantity { quantity: u64, timestamp: i64 },
+    Lot { parents: Vec<LotParent>, timestamp: i64 }, // First entry of a split or merged lot
+    Split { lots: Vec<Pubkey>, timestamp: i64 },     // Last entry of a lot divided into `lots`
+    Merged { lot: Pubkey, timestamp: i64 },          // Last entry of a lot pooled into `lot`
+}
+
+// Pins the parent's history as it stood when the lot was taken from it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct LotParent {
+    pub product: Pubkey,
+    pub history_hash: [u8; 32],
+    pub quantity: u64,               // Taken from this parent
+}
+
+impl HistoryEntry {
+    pub fn chain(&self, prev: &[u8; 32]) -> [u8; 32] {
+        hashv(&[prev, &self.try_to_vec().unwrap_or_default()]).to_bytes()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct GrowthUpdate {
+    pub stage: GrowthStage,
+    pub timestamp: i64,
+    pub notes: String,
+    pub firebase_image_urls: Vec<String>, // Unhashed, only on migrated updates
+    pub media: Vec<MediaRef>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct MediaRef {
+    pub uri: String,
+    pub sha256: [u8; 32],
+    pub mime: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct DeliveryUpdate {
+    pub status: DeliveryStatus,
+    pub timestamp: i64,
+    pub notes: String,
+    pub location: Option<String>,
+    pub checkpoint: Option<GeoCheckpoint>,
+    pub signed_by: Pubkey,               // Farmer, staff, courier or buyer
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct GeoCheckpoint {
+    pub geohash: String,
+    pub recorded_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct CoopMember {
+    pub farmer: Pubkey,
+    pub profile: Pubkey,             // The member's FarmerProfile
+    pub joined_at: i64,
+    pub open_contributions: u16,     // Coop products supplied that are not closed yet
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct MemberContribution {
+    pub farmer: Pubkey,
+    pub quantity: u64,               // In the coop product's unit
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct FundingOptions {
+    pub allow_overfunding: bool,
+    pub hard_cap: Option<u64>,
+    pub stretch_goals: Vec<StretchGoal>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct StretchGoal {
+    pub amount: u64,
+    pub milestone: String,           // What the extra money pays for
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct Beneficiary {
+    pub wallet: Pubkey,
+    pub share_bps: u16,              // Out of BPS_DENOMINATOR
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct Contributor {
+    pub wallet: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum GrowthStage {
+    Seeding,
+    Germination,
+    Growing,
+    Flowering,
+    Fruiting,
+    Harvest,
+    PostHarvest,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum DeliveryStatus {
+    Preparing,
+    Packed,
+    InTransit,
+    Delivered,
+    Completed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum CampaignType {
+    Equipment,
+    Seeds,
+    Infrastructure,
+    Expansion,
+    Emergency,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ProductCategory {
+    Vegetables,
+    Fruits,
+    Dairy,
+    Meat,
+    Eggs,
+    Grains,
+    Honey,
+    Herbs,
+    Preserves,
+    Other,
+}
+
+// Grouped by parent category; keep `category()` in step when adding variants
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ProductSubcategory {
+    LeafyGreens,
+    RootVegetables,
+    Brassicas,
+    Nightshades,
+    Legumes,
+    Berries,
+    OrchardFruit,
+    StoneFruit,
+    Milk,
+    Cheese,
+    Yogurt,
+    Butter,
+    Beef,
+    Pork,
+    Poultry,
+    Lamb,
+    ChickenEggs,
+    DuckEggs,
+    Cereals,
+    Flour,
+}
+
+impl ProductSubcategory {
+    pub fn category(&self) -> ProductCategory {
+        match self {
+            Self::LeafyGreens
+            | Self::RootVegetables
+            | Self::Brassicas
+            | Self::Nightshades
+            | Self::Legumes => ProductCategory::Vegetables,
+            Self::Berries | Self::OrchardFruit | Self::StoneFruit => ProductCategory::Fruits,
+            Self::Milk | Self::Cheese | Self::Yogurt | Self::Butter => ProductCategory::Dairy,
+            Self::Beef | Self::Pork | Self::Poultry | Self::Lamb => ProductCategory::Meat,
+            Self::ChickenEggs | Self::DuckEggs => ProductCategory::Eggs,
+            Self::Cereals | Self::Flour => ProductCategory::Grains,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum UnitOfMeasure {
+    Kilogram,
+    Piece,
+    Litre,
+    Box,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LotStatus {
+    Active,
+    Split,                           // Divided into child lots
+    Merged,                          // Pooled into another lot
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum PayoutSource {
+    Sale,
+    Crowdfunding,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum StaffPermission {
+    GrowthUpdates,
+    DeliveryUpdates,
+    ProductCreation,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum PausableFeature {
+    Crowdfunding,
+    Orders,
+    ProfileWrites,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum FeeKind {
+    CampaignWithdrawal,
+    PreOrderSettlement,
+    SubscriptionInstalment,
+}
+
+// Events
+#[event]
+pub struct HarvestSharesRedeemed {
+    pub campaign: Pubkey,
+    pub holder: Pubkey,
+    pub shares: u64,
+    pub quantity: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HistoryRecorded {
+    pub product: Pubkey,
+    pub entry: HistoryEntry,
+    pub history_hash: [u8; 32],
+}
+
+#[event]
+pub struct CoopProceedsDeposited {
+    pub cooperative: Pubkey,
+    pub product: Pubkey,
+    pub depositor: Pubkey,
+    pub source: PayoutSource,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CoopPayoutDistributed {
+    pub cooperative: Pubkey,
+    pub product: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlatformFeeCollected {
+    pub kind: FeeKind,
+    pub source: Pubkey,
+    pub farmer: Pubkey,
+    pub gross_amount: u64,
+    pub fee_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PersonalDataErased {
+    pub farmer: Pubkey,
+    pub grants_revoked: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CampaignCancelled {
+    pub campaign: Pubkey,
+    pub farmer: Pubkey,
+    pub refundable_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StretchGoalReached {
+    pub campaign: Pubkey,
+    pub index: u8,
+    pub amount: u64,
+    pub milestone: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CampaignDeadlineExtended {
+    pub campaign: Pubkey,
+    pub previous_deadline: i64,
+    pub new_deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PauseToggled {
+    pub feature: Option<PausableFeature>, // None for the global flag
+    pub paused: bool,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub admin: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFarmer<'info> {
+    #[account(
+        init, 
         payer = farmer, 
-        space = 8 + 64 + 32 + 128 + 64 + 256 + 8 + 8 + 8 + 512 + 2048 + 2048 + 8 + 8,
+        space = FarmerProfile::SPACE,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    /// CHECK: Empty unless this wallet closed a profile before; read in the handler
+    #[account(
+        mut,
+        seeds = [b"farmer_tombstone", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_tombstone: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFarmerProfile<'info> {
+    #[account(
+        mut, 
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        constraint = !farmer_profile.anonymised @ ErrorCode::ProfileAnonymised,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProduct<'info> {
+    #[account(
+        init, 
+        payer = authority, 
+        space = ProductCycle::SPACE,
         seeds = [b"product", farmer.key().as_ref(), &Clock::get().unwrap().unix_timestamp.to_le_bytes()[0..8]],
         bump
     )]
     pub product_cycle: Account<'info, ProductCycle>,
     #[account(
-        mut, 
+        mut, 
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(
+        seeds = [b"delegate", farmer.key().as_ref()],
+        bump = delegate.bump
+    )]
+    pub delegate: Option<Account<'info, Delegate>>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// CHECK: Product owner, pinned by the farmer profile seeds
+    pub farmer: UncheckedAccount<'info>,
+    // The farmer or a staff key with product creation rights; pays the rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProduct<'info> {
+    #[account(mut)]
+    pub product_cycle: Account<'info, ProductCycle>,
+    #[account(
+        seeds = [b"delegate", product_cycle.farmer.as_ref()],
+        bump = delegate.bump
+    )]
+    pub delegate: Option<Account<'info, Delegate>>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    // The farmer, or a staff key for the instructions that accept one
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateActualQuantity<'info> {
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer
+    )]
+    pub product_cycle: Account<'info, ProductCycle>,
+    #[account(
+        mut,
+        seeds = [b"preorder", product_cycle.key().as_ref()],
+        bump = pre_order_listing.bump
+    )]
+    pub pre_order_listing: Option<Account<'info, PreOrderListing>>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddDeliveryUpdate<'info> {
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer
+    )]
+    pub product_cycle: Account<'info, ProductCycle>,
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        has_one = product_cycle @ ErrorCode::SubscriptionProductMismatch,
+        seeds = [b"subscription", farmer.key().as_ref(), subscription.buyer.as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Option<Account<'info, Subscription>>,
+    /// CHECK: The subscription's vault, derived from its stored bump
+    #[account(
+        mut,
+        constraint = subscription
+            .as_ref()
+            .and_then(|sub| sub.vault_address(&sub.key()))
+            == Some(subscription_vault.key()) @ ErrorCode::InvalidSubscriptionVault
+    )]
+    pub subscription_vault: Option<AccountInfo<'info>>,
+    // Gated in the handler: completing a subscription delivery settles an instalment
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// CHECK: Platform treasury vault
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = platform_config.treasury_bump
+    )]
+    pub treasury: Option<AccountInfo<'info>>,
+    #[account(
+        seeds = [b"delegate", farmer.key().as_ref()],
+        bump = delegate.bump
+    )]
+    pub delegate: Option<Account<'info, Delegate>>,
+    #[account(
+        seeds = [b"courier", product_cycle.key().as_ref()],
+        bump = courier_assignment.bump
+    )]
+    pub courier_assignment: Option<Account<'info, CourierAssignment>>,
+    /// CHECK: Product owner and subscription payee, pinned by `has_one`
+    #[account(mut)]
+    pub farmer: UncheckedAccount<'info>,
+    // The farmer, a staff key, the assigned courier or the buyer, by status
+    pub authority: Signer<'info>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCrowdfundingCampaign<'info> {
+    #[account(
+        init, 
+        payer = farmer, 
+        space = CrowdfundingCampaign::SPACE,
+        seeds = [b"campaign", farmer.key().as_ref(), &Clock::get().unwrap().unix_timestamp.to_le_bytes()[0..8]],
+        bump
+    )]
+    pub campaign: Account<'info, CrowdfundingCampaign>,
+    /// CHECK: Safe vault account
+    #[account(
+        mut,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump
+    )]
+    pub campaign_vault: AccountInfo<'info>,
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::Crowdfunding) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ContributeToCampaign<'info> {
+    #[account(mut)]
+    pub campaign: Account<'info, CrowdfundingCampaign>,
+    /// CHECK: Safe vault account
+    #[account(
+        mut,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump
+    )]
+    pub campaign_vault: AccountInfo<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::Crowdfunding) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateFarmerProfile<'info> {
+    /// CHECK: Legacy layouts do not deserialize as `FarmerProfile`; parsed by `migration`
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateProduct<'info> {
+    /// CHECK: Legacy layouts do not deserialize as `ProductCycle`; parsed by `migration`
+    #[account(mut, owner = crate::ID)]
+    pub product_cycle: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateCampaign<'info> {
+    /// CHECK: Legacy layouts do not deserialize as `CrowdfundingCampaign`; parsed by `migration`
+    #[account(mut, owner = crate::ID)]
+    pub campaign: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::Crowdfunding) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPreOrder<'info> {
+    #[account(
+        init,
+        payer = farmer,
+        space = PreOrderListing::SPACE,
+        seeds = [b"preorder", product_cycle.key().as_ref()],
+        bump
+    )]
+    pub pre_order_listing: Account<'info, PreOrderListing>,
+    /// CHECK: Safe vault account
+    #[account(
+        mut,
+        seeds = [b"preorder_vault", pre_order_listing.key().as_ref()],
+        bump
+    )]
+    pub pre_order_vault: AccountInfo<'info>,
+    #[account(has_one = farmer @ ErrorCode::UnauthorizedFarmer)]
+    pub product_cycle: Account<'info, ProductCycle>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::Orders) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReservePreOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"preorder", pre_order_listing.product.as_ref()],
+        bump = pre_order_listing.bump
+    )]
+    pub pre_order_listing: Account<'info, PreOrderListing>,
+    /// CHECK: Safe vault account
+    #[account(
+        mut,
+        seeds = [b"preorder_vault", pre_order_listing.key().as_ref()],
+        bump = pre_order_listing.vault_bump
+    )]
+    pub pre_order_vault: AccountInfo<'info>,
+    #[account(
+        init,
+        payer = buyer,
+        space = Reservation::SPACE,
+        seeds = [b"reservation", pre_order_listing.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub reservation: Account<'info, Reservation>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::Orders) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleReservation<'info> {
+    #[account(
+        mut,
+        seeds = [b"preorder", pre_order_listing.product.as_ref()],
+        bump = pre_order_listing.bump
+    )]
+    pub pre_order_listing: Account<'info, PreOrderListing>,
+    /// CHECK: Safe vault account
+    #[account(
+        mut,
+        seeds = [b"preorder_vault", pre_order_listing.key().as_ref()],
+        bump = pre_order_listing.vault_bump
+    )]
+    pub pre_order_vault: AccountInfo<'info>,
+    #[account(
+        mut,
+        has_one = buyer,
+        close = buyer,
+        seeds = [b"reservation", pre_order_listing.key().as_ref(), buyer.key().as_ref()],
+        bump = reservation.bump
+    )]
+    pub reservation: Account<'info, Reservation>,
+    /// CHECK: Receives the refund; bound to the reservation by `has_one`
+    #[account(mut)]
+    pub buyer: AccountInfo<'info>,
+    /// CHECK: Receives the payment; must be the listing's farmer
+    #[account(mut, address = pre_order_listing.farmer @ ErrorCode::UnauthorizedFarmer)]
+    pub farmer: AccountInfo<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// CHECK: Platform treasury vault
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = platform_config.treasury_bump
+    )]
+    pub treasury: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSubscription<'info> {
+    #[account(
+        init,
+        payer = buyer,
+        space = Subscription::SPACE,
+        seeds = [b"subscription", farmer_profile.farmer.as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    /// CHECK: Safe vault account
+    #[account(
+        mut,
+        seeds = [b"subscription_vault", subscription.key().as_ref()],
+        bump
+    )]
+    pub subscription_vault: AccountInfo<'info>,
+    #[account(
+        seeds = [b"farmer_profile", farmer_profile.farmer.as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(
+        mut,
+        constraint = product_cycle.farmer == farmer_profile.farmer @ ErrorCode::UnauthorizedFarmer
+    )]
+    pub product_cycle: Account<'info, ProductCycle>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::Orders) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    #[account(
+        mut,
+        has_one = buyer,
+        close = buyer,
+        seeds = [b"subscription", subscription.farmer.as_ref(), buyer.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    /// CHECK: Safe vault account
+    #[account(
+        mut,
+        seeds = [b"subscription_vault", subscription.key().as_ref()],
+        bump = subscription.vault_bump
+    )]
+    pub subscription_vault: AccountInfo<'info>,
+    #[account(
+        mut,
+        address = subscription.product_cycle @ ErrorCode::SubscriptionProductMismatch
+    )]
+    pub product_cycle: Option<Account<'info, ProductCycle>>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnableHarvestShares<'info> {
+    #[account(has_one = farmer @ ErrorCode::UnauthorizedFarmer)]
+    pub campaign: Account<'info, CrowdfundingCampaign>,
+    #[account(mut, has_one = farmer @ ErrorCode::UnauthorizedFarmer)]
+    pub product_cycle: Account<'info, ProductCycle>,
+    #[account(
+        init,
+        payer = farmer,
+        space = HarvestShares::SPACE,
+        seeds = [b"harvest_shares", campaign.key().as_ref()],
+        bump
+    )]
+    pub harvest_shares: Account<'info, HarvestShares>,
+    #[account(
+        init,
+        payer = farmer,
+        seeds = [b"harvest_share_mint", campaign.key().as_ref()],
+        bump,
+        mint::decimals = 9,
+        mint::authority = harvest_shares
+    )]
+    pub share_mint: Account<'info, Mint>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::Crowdfunding) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimHarvestShares<'info> {
+    pub campaign: Account<'info, CrowdfundingCampaign>,
+    #[account(
+        mut,
+        has_one = campaign,
+        has_one = share_mint,
+        seeds = [b"harvest_shares", campaign.key().as_ref()],
+        bump = harvest_shares.bump
+    )]
+    pub harvest_shares: Account<'info, HarvestShares>,
+    #[account(mut)]
+    pub share_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = contributor,
+        space = ShareClaim::SPACE,
+        seeds = [b"share_claim", campaign.key().as_ref(), contributor.key().as_ref()],
+        bump
+    )]
+    pub share_claim: Account<'info, ShareClaim>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = contributor
+    )]
+    pub contributor_token_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::Crowdfunding) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemHarvestShares<'info> {
+    pub campaign: Account<'info, CrowdfundingCampaign>,
+    #[account(
+        mut,
+        has_one = campaign,
+        has_one = product_cycle,
+        has_one = share_mint,
+        seeds = [b"harvest_shares", campaign.key().as_ref()],
+        bump = harvest_shares.bump
+    )]
+    pub harvest_shares: Account<'info, HarvestShares>,
+    pub product_cycle: Account<'info, ProductCycle>,
+    #[account(mut)]
+    pub share_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = holder
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    pub holder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MintBatchCertificate<'info> {
+    #[account(has_one = farmer @ ErrorCode::UnauthorizedFarmer)]
+    pub product_cycle: Account<'info, ProductCycle>,
+    #[account(
+        init,
+        payer = farmer,
+        seeds = [b"batch_certificate", product_cycle.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = certificate_mint,
+        mint::token_program = token_program,
+        extensions::metadata_pointer::authority = certificate_mint,
+        extensions::metadata_pointer::metadata_address = certificate_mint
+    )]
+    pub certificate_mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        init,
+        payer = farmer,
+        associated_token::mint = certificate_mint,
+        associated_token::authority = farmer,
+        associated_token::token_program = token_program
+    )]
+    pub farmer_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePlatformConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = PlatformConfig::SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// CHECK: Platform treasury vault
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: AccountInfo<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::FarmDirectBlockchain>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::UnauthorizedAdmin)]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePlatformConfig<'info> {
+    #[account(
+        mut,
+        has_one = admin @ ErrorCode::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = platform_config.bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = platform_config.can_pause(&authority.key()) @ ErrorCode::UnauthorizedAdmin
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        has_one = admin @ ErrorCode::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = platform_config.bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// CHECK: Platform treasury vault
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = platform_config.treasury_bump
+    )]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: Any wallet the admin chooses to pay out to
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCampaignFunds<'info> {
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer
+    )]
+    pub campaign: Account<'info, CrowdfundingCampaign>,
+    /// CHECK: Safe vault account
+    #[account(
+        mut,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump
+    )]
+    pub campaign_vault: AccountInfo<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// CHECK: Platform treasury vault
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = platform_config.treasury_bump
+    )]
+    pub treasury: AccountInfo<'info>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageCampaign<'info> {
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer
+    )]
+    pub campaign: Account<'info, CrowdfundingCampaign>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::Crowdfunding) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub farmer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelCampaign<'info> {
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer
+    )]
+    pub campaign: Account<'info, CrowdfundingCampaign>,
+    /// CHECK: Read if present; claimed shares rule out a cancellation
+    #[account(
+        seeds = [b"harvest_shares", campaign.key().as_ref()],
+        bump
+    )]
+    pub harvest_shares: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::Crowdfunding) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub farmer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(mut)]
+    pub campaign: Account<'info, CrowdfundingCampaign>,
+    /// CHECK: Safe vault account
+    #[account(
+        mut,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump
+    )]
+    pub campaign_vault: AccountInfo<'info>,
+    /// CHECK: Must be empty; backers who claimed harvest shares keep those instead
+    #[account(
+        seeds = [b"share_claim", campaign.key().as_ref(), contributor.key().as_ref()],
+        bump
+    )]
+    pub share_claim: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseProduct<'info> {
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        has_one = rent_payer @ ErrorCode::InvalidRentPayer,
+        close = rent_payer
+    )]
+    pub product_cycle: Account<'info, ProductCycle>,
+    /// CHECK: Whoever paid the product's rent, pinned by `has_one`
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+    /// CHECK: Empty unless the product has a pre-order; read in the handler
+    #[account(
+        mut,
+        seeds = [b"preorder", product_cycle.key().as_ref()],
+        bump
+    )]
+    pub pre_order_listing: UncheckedAccount<'info>,
+    /// CHECK: The listing's vault, swept to the farmer when the listing closes
+    #[account(
+        mut,
+        seeds = [b"preorder_vault", pre_order_listing.key().as_ref()],
+        bump
+    )]
+    pub pre_order_vault: UncheckedAccount<'info>,
+    /// CHECK: Must be empty; a minted certificate keeps the product alive
+    #[account(
+        seeds = [b"batch_certificate", product_cycle.key().as_ref()],
+        bump
+    )]
+    pub certificate_mint: UncheckedAccount<'info>,
+    /// CHECK: Must be empty; released with `release_courier`
+    #[account(
+        seeds = [b"courier", product_cycle.key().as_ref()],
+        bump
+    )]
+    pub courier_assignment: UncheckedAccount<'info>,
+    /// CHECK: Empty unless the product is a coop listing; closed in the handler
+    #[account(
+        mut,
+        seeds = [b"coop_product", product_cycle.key().as_ref()],
+        bump
+    )]
+    pub coop_product: UncheckedAccount<'info>,
+    /// CHECK: The coop listing's vault, swept to the farmer once distributed
+    #[account(
+        mut,
+        seeds = [b"coop_vault", coop_product.key().as_ref()],
+        bump
+    )]
+    pub coop_vault: UncheckedAccount<'info>,
+    /// CHECK: The farmer's cooperative, if any; its members' contributions close with a coop listing
+    #[account(
+        mut,
+        seeds = [b"cooperative", farmer.key().as_ref()],
+        bump
+    )]
+    pub cooperative: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCampaign<'info> {
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        close = farmer
+    )]
+    pub campaign: Account<'info, CrowdfundingCampaign>,
+    /// CHECK: Safe vault account
+    #[account(
+        mut,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump
+    )]
+    pub campaign_vault: AccountInfo<'info>,
+    /// CHECK: Must be empty; campaigns that issued harvest shares stay open
+    #[account(
+        seeds = [b"harvest_shares", campaign.key().as_ref()],
+        bump
+    )]
+    pub harvest_shares: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::Crowdfunding) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseFarmerProfile<'info> {
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        close = farmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(
+        init,
+        payer = farmer,
+        space = FarmerTombstone::SPACE,
+        seeds = [b"farmer_tombstone", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_tombstone: Account<'info, FarmerTombstone>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AssignCourier<'info> {
+    #[account(
+        init,
+        payer = farmer,
+        space = CourierAssignment::SPACE,
+        seeds = [b"courier", product_cycle.key().as_ref()],
+        bump
+    )]
+    pub courier_assignment: Account<'info, CourierAssignment>,
+    #[account(has_one = farmer @ ErrorCode::UnauthorizedFarmer)]
+    pub product_cycle: Account<'info, ProductCycle>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageCourier<'info> {
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"courier", courier_assignment.product.as_ref()],
+        bump = courier_assignment.bump
+    )]
+    pub courier_assignment: Account<'info, CourierAssignment>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub farmer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseCourier<'info> {
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        close = farmer,
+        seeds = [b"courier", courier_assignment.product.as_ref()],
+        bump = courier_assignment.bump
+    )]
+    pub courier_assignment: Account<'info, CourierAssignment>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeDelegate<'info> {
+    #[account(
+        init,
+        payer = farmer,
+        space = Delegate::SPACE,
+        seeds = [b"delegate", farmer.key().as_ref()],
+        bump
+    )]
+    pub delegate: Account<'info, Delegate>,
+    #[account(
         has_one = farmer @ ErrorCode::UnauthorizedFarmer,
         seeds = [b"farmer_profile", farmer.key().as_ref()],
         bump
     )]
     pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
     #[account(mut)]
     pub farmer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateProduct<'info> {
+pub struct ManageDelegate<'info> {
     #[account(
-        mut, 
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"delegate", farmer.key().as_ref()],
+        bump = delegate.bump
+    )]
+    pub delegate: Account<'info, Delegate>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub farmer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(buyer: Pubkey)]
+pub struct GrantAccess<'info> {
+    #[account(
+        init,
+        payer = farmer,
+        space = AccessGrant::SPACE,
+        seeds = [b"access_grant", farmer.key().as_ref(), buyer.as_ref()],
+        bump
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        constraint = !farmer_profile.anonymised @ ErrorCode::ProfileAnonymised,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAccess<'info> {
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        close = farmer,
+        seeds = [b"access_grant", farmer.key().as_ref(), access_grant.buyer.as_ref()],
+        bump = access_grant.bump
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+}
+
+// Deliberately not gated on the pause flags
+#[derive(Accounts)]
+pub struct ErasePersonalData<'info> {
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyProvenance<'info> {
+    pub product_cycle: Account<'info, ProductCycle>,
+    #[account(
+        seeds = [b"farmer_profile", product_cycle.farmer.as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+}
+
+#[derive(Accounts)]
+pub struct SplitBatch<'info> {
+    #[account(
+        mut,
         has_one = farmer @ ErrorCode::UnauthorizedFarmer
     )]
     pub product_cycle: Account<'info, ProductCycle>,
+    /// CHECK: Empty unless the product has a pre-order; read in the handler
+    #[account(
+        seeds = [b"preorder", product_cycle.key().as_ref()],
+        bump
+    )]
+    pub pre_order_listing: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
     #[account(mut)]
     pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CreateCrowdfundingCampaign<'info> {
+pub struct MergeBatches<'info> {
     #[account(
-        init, 
-        payer = farmer, 
-        space = 8 + 64 + 32 + 128 + 256 + 8 + 8 + 8 + 64 + 512 + 2048 + 1 + 8 + 8,
-        seeds = [b"campaign", farmer.key().as_ref(), &Clock::get().unwrap().unix_timestamp.to_le_bytes()[0..8]],
+        init,
+        payer = farmer,
+        space = ProductCycle::SPACE,
+        seeds = [b"merged_lot", farmer.key().as_ref(), &Clock::get().unwrap().unix_timestamp.to_le_bytes()[0..8]],
         bump
     )]
-    pub campaign: Account<'info, CrowdfundingCampaign>,
-    /// CHECK: Safe vault account
+    pub lot: Account<'info, ProductCycle>,
     #[account(
         mut,
-        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
         bump
     )]
-    pub campaign_vault: AccountInfo<'info>,
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    // Owns the merged lot, e.g. the cooperative that sells it; pays the rent
     #[account(mut)]
     pub farmer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ContributeToCampaign<'info> {
+pub struct CreateCooperative<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Cooperative::SPACE,
+        seeds = [b"cooperative", admin.key().as_ref()],
+        bump
+    )]
+    pub cooperative: Account<'info, Cooperative>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
     #[account(mut)]
-    pub campaign: Account<'info, CrowdfundingCampaign>,
-    /// CHECK: Safe vault account
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageMembership<'info> {
+    #[account(
+        mut,
+        has_one = admin @ ErrorCode::UnauthorizedCoopAdmin,
+        seeds = [b"cooperative", admin.key().as_ref()],
+        bump = cooperative.bump
+    )]
+    pub cooperative: Account<'info, Cooperative>,
+    #[account(
+        has_one = farmer @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub farmer: Signer<'info>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterCoopProduct<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = CoopProduct::SPACE,
+        seeds = [b"coop_product", product_cycle.key().as_ref()],
+        bump
+    )]
+    pub coop_product: Account<'info, CoopProduct>,
+    #[account(
+        mut,
+        seeds = [b"coop_vault", coop_product.key().as_ref()],
+        bump
+    )]
+    pub coop_vault: SystemAccount<'info>,
+    #[account(
+        constraint = product_cycle.farmer == admin.key() @ ErrorCode::UnauthorizedCoopAdmin
+    )]
+    pub product_cycle: Account<'info, ProductCycle>,
+    #[account(
+        mut,
+        has_one = admin @ ErrorCode::UnauthorizedCoopAdmin,
+        seeds = [b"cooperative", admin.key().as_ref()],
+        bump = cooperative.bump
+    )]
+    pub cooperative: Account<'info, Cooperative>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::ProfileWrites) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
     #[account(mut)]
-    pub campaign_vault: AccountInfo<'info>,
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCoopProceeds<'info> {
+    #[account(
+        seeds = [b"coop_product", coop_product.product.as_ref()],
+        bump = coop_product.bump
+    )]
+    pub coop_product: Account<'info, CoopProduct>,
+    #[account(
+        mut,
+        seeds = [b"coop_vault", coop_product.key().as_ref()],
+        bump = coop_product.vault_bump
+    )]
+    pub coop_vault: SystemAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = platform_config.bump,
+        constraint = !platform_config.is_paused(PausableFeature::Orders) @ ErrorCode::ProgramPaused
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
     #[account(mut)]
-    pub contributor: Signer<'info>,
+    pub depositor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeCoopPayout<'info> {
+    #[account(
+        mut,
+        has_one = cooperative @ ErrorCode::UnauthorizedCoopAdmin,
+        seeds = [b"coop_product", coop_product.product.as_ref()],
+        bump = coop_product.bump
+    )]
+    pub coop_product: Account<'info, CoopProduct>,
+    #[account(
+        mut,
+        seeds = [b"coop_vault", coop_product.key().as_ref()],
+        bump = coop_product.vault_bump
+    )]
+    pub coop_vault: SystemAccount<'info>,
+    #[account(
+        has_one = admin @ ErrorCode::UnauthorizedCoopAdmin,
+        seeds = [b"cooperative", admin.key().as_ref()],
+        bump = cooperative.bump
+    )]
+    pub cooperative: Account<'info, Cooperative>,
+    pub admin: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -423,4 +4846,146 @@ pub enum ErrorCode {
     CampaignDeadlineExceeded,
     #[msg("Invalid amount")]
     InvalidAmount,
-}
\ No newline at end of file
+    #[msg("Account is already at the current layout version")]
+    AccountAlreadyMigrated,
+    #[msg("Account data does not match any known layout")]
+    UnknownAccountLayout,
+    #[msg("Pre-order is closed")]
+    PreOrderClosed,
+    #[msg("Not enough reservable quantity left")]
+    ExceedsReservableQuantity,
+    #[msg("Harvest has already been recorded")]
+    HarvestAlreadyRecorded,
+    #[msg("Harvest has not been recorded yet")]
+    HarvestNotRecorded,
+    #[msg("Subscription is not active")]
+    SubscriptionNotActive,
+    #[msg("Subscription vault, treasury and system program are required")]
+    MissingSubscriptionAccounts,
+    #[msg("Harvest shares are only available for seed and expansion campaigns")]
+    HarvestSharesNotSupported,
+    #[msg("Campaign is still accepting contributions")]
+    CampaignStillActive,
+    #[msg("Wallet has not contributed to this campaign")]
+    NotAContributor,
+    #[msg("Unauthorized admin")]
+    UnauthorizedAdmin,
+    #[msg("Platform fee exceeds the maximum")]
+    FeeTooHigh,
+    #[msg("Campaign goal has not been reached")]
+    CampaignGoalNotReached,
+    #[msg("This feature is paused")]
+    ProgramPaused,
+    #[msg("Campaign has been cancelled")]
+    CampaignCancelled,
+    #[msg("Campaign funds have already been withdrawn")]
+    CampaignFundsWithdrawn,
+    #[msg("Campaign details are locked once contributions arrive")]
+    CampaignHasContributions,
+    #[msg("Campaign deadline has already been extended")]
+    DeadlineAlreadyExtended,
+    #[msg("Deadline extension is out of range")]
+    InvalidDeadlineExtension,
+    #[msg("Campaign is not open for refunds")]
+    CampaignNotRefundable,
+    #[msg("Product has pre-order reservations awaiting settlement")]
+    PreOrderUnsettled,
+    #[msg("Product has a batch certificate")]
+    ProductHasCertificate,
+    #[msg("Campaign has issued harvest shares")]
+    CampaignHasHarvestShares,
+    #[msg("Campaign vault still holds contributions")]
+    CampaignNotSettled,
+    #[msg("Farmer still has active products or campaigns")]
+    FarmerHasActiveListings,
+    #[msg("Price must be greater than zero")]
+    InvalidPrice,
+    #[msg("Minimum order quantity must be greater than zero")]
+    InvalidMinOrderQuantity,
+    #[msg("Not enough stock")]
+    InsufficientStock,
+    #[msg("Subcategory does not belong to the category")]
+    InvalidSubcategory,
+    #[msg("Region code must be a NUTS or TERYT code")]
+    InvalidRegionCode,
+    #[msg("Invalid or too precise geohash")]
+    InvalidGeohash,
+    #[msg("Checkpoint time is in the future")]
+    InvalidCheckpointTime,
+    #[msg("Media must use a supported URI, MIME type and a SHA-256 hash")]
+    InvalidMediaRef,
+    #[msg("Staff key lacks this permission or has expired")]
+    UnauthorizedStaff,
+    #[msg("Staff key expiry must be in the future")]
+    InvalidStaffExpiry,
+    #[msg("The farmer cannot be added as staff")]
+    InvalidStaffKey,
+    #[msg("Too many staff keys")]
+    TooManyStaffKeys,
+    #[msg("Staff key not found")]
+    StaffKeyNotFound,
+    #[msg("The farmer cannot be assigned as courier")]
+    InvalidCourier,
+    #[msg("Delivery must be confirmed by the courier or the buyer")]
+    UnauthorizedDeliveryConfirmation,
+    #[msg("Courier updates must include a checkpoint")]
+    CheckpointRequired,
+    #[msg("Data is not a valid encryption envelope")]
+    InvalidEnvelope,
+    #[msg("Profile has been anonymised")]
+    ProfileAnonymised,
+    #[msg("Split needs two to eight positive quantities summing to the harvest")]
+    InvalidSplit,
+    #[msg("Lot has already been split or merged")]
+    LotNotActive,
+    #[msg("Child lot account does not match its seeds")]
+    InvalidLotAccount,
+    #[msg("Merge needs two to eight distinct parent lots, each with its farmer")]
+    InvalidMerge,
+    #[msg("Merged lots must share a category")]
+    LotCategoryMismatch,
+    #[msg("Cooperative name must be 1 to 64 bytes")]
+    InvalidCooperativeName,
+    #[msg("Unauthorized cooperative admin")]
+    UnauthorizedCoopAdmin,
+    #[msg("Farmer is already a member")]
+    AlreadyMember,
+    #[msg("Cooperative has too many members")]
+    TooManyMembers,
+    #[msg("Farmer is not a member of the cooperative")]
+    NotAMember,
+    #[msg("Contributions must list distinct members with positive quantities")]
+    InvalidContributions,
+    #[msg("Beneficiaries must be distinct wallets with shares summing to 10000 bps")]
+    InvalidBeneficiaries,
+    #[msg("Only equipment campaigns can have several beneficiaries")]
+    JointCampaignNotSupported,
+    #[msg("Stretch goals and hard cap need overfunding and must rise above the goal")]
+    InvalidFundingOptions,
+    #[msg("Contribution would exceed the campaign's hard cap")]
+    ExceedsHardCap,
+    #[msg("Delivery must be confirmed by the courier or the buyer before it is completed")]
+    DeliveryNotConfirmed,
+    #[msg("Subscription is for another product")]
+    SubscriptionProductMismatch,
+    #[msg("Subscription vault does not match the subscription")]
+    InvalidSubscriptionVault,
+    #[msg("Contribution was already converted into harvest shares")]
+    SharesAlreadyClaimed,
+    #[msg("Product backs harvest shares")]
+    ProductHasHarvestShares,
+    #[msg("Release the product's courier assignment first")]
+    ProductHasCourier,
+    #[msg("Revoke every access grant before erasing personal data")]
+    AccessGrantsOutstanding,
+    #[msg("Distribute the coop product's proceeds before closing it")]
+    CoopProceedsUndistributed,
+    #[msg("Complete the confirmed delivery before cancelling the subscription")]
+    InstalmentAwaitingRelease,
+    #[msg("Product has active subscriptions")]
+    ProductHasSubscriptions,
+    #[msg("Close the coop products the member supplied before they leave")]
+    CoopContributionsOpen,
+    #[msg("Rent goes back to the account that paid it")]
+    InvalidRentPayer,
+}
@@ -0,0 +1,382 @@
+// farm-direct-blockchain/programs/farm-direct-blockchain/src/migration.rs
+//
+// Snapshots of deployed account layouts and the code that upgrades them in
+// place. Version 1 is the original, unversioned layout; every later layout
+// starts with a `version` byte. Each deployed layout upgrades one version at a
+// time until the current one is reached.
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use std::ops::ControlFlow;
+
+use crate::{
+    history_genesis, Beneficiary, CampaignType, Contributor, CrowdfundingCampaign,
+    DeliveryStatus, DeliveryUpdate, ErrorCode, FarmerProfile, GrowthStage, GrowthUpdate,
+    HistoryEntry, LotStatus, ProductCategory, ProductCycle, UnitOfMeasure, BPS_DENOMINATOR,
+    CAMPAIGN_VERSION, FARMER_PROFILE_VERSION, PRODUCT_CYCLE_VERSION,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct FarmerProfileV1 {
+    pub farmer: Pubkey,
+    pub encrypted_data: String,
+    pub public_name: String,
+    pub region: String,
+    pub certifications: Vec<String>,
+    pub verification_status: bool,
+    pub reputation_score: u64,
+    pub total_products: u64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ProductCycleV1 {
+    pub product_id: String,
+    pub farmer: Pubkey,
+    pub product_name: String,
+    pub category: String,
+    pub description: String,
+    pub estimated_harvest_date: i64,
+    pub estimated_quantity: u64,
+    pub actual_quantity: u64,
+    pub firebase_image_urls: Vec<String>,
+    pub growth_updates: Vec<GrowthUpdateV1>,
+    pub delivery_updates: Vec<DeliveryUpdateV1>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+// Growth entry before hashed media
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct GrowthUpdateV1 {
+    pub stage: GrowthStage,
+    pub timestamp: i64,
+    pub notes: String,
+    pub firebase_image_urls: Vec<String>,
+}
+
+// Delivery entry before geohash checkpoints and signer attribution
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct DeliveryUpdateV1 {
+    pub status: DeliveryStatus,
+    pub timestamp: i64,
+    pub notes: String,
+    pub location: Option<String>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct CrowdfundingCampaignV1 {
+    pub campaign_id: String,
+    pub farmer: Pubkey,
+    pub title: String,
+    pub description: String,
+    pub goal_amount: u64,
+    pub current_amount: u64,
+    pub deadline: i64,
+    pub campaign_type: CampaignType,
+    pub milestones: Vec<String>,
+    pub contributors: Vec<Contributor>,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+// A deployed, superseded layout of one account type
+trait Layout: Sized {
+    type Current;
+
+    // Upgrades by one version: to the next deployed layout, or to the current one
+    fn step(self) -> ControlFlow<Self::Current, Self>;
+}
+
+fn upgrade<L: Layout>(mut layout: L) -> L::Current {
+    loop {
+        match layout.step() {
+            ControlFlow::Continue(next) => layout = next,
+            ControlFlow::Break(current) => return current,
+        }
+    }
+}
+
+enum FarmerProfileLayout {
+    V1(FarmerProfileV1),
+}
+
+impl Layout for FarmerProfileLayout {
+    type Current = FarmerProfile;
+
+    fn step(self) -> ControlFlow<FarmerProfile, Self> {
+        match self {
+            Self::V1(v1) => ControlFlow::Break(v1.into()),
+        }
+    }
+}
+
+enum ProductCycleLayout {
+    V1(ProductCycleV1),
+}
+
+impl Layout for ProductCycleLayout {
+    type Current = ProductCycle;
+
+    fn step(self) -> ControlFlow<ProductCycle, Self> {
+        match self {
+            Self::V1(v1) => ControlFlow::Break(v1.into()),
+        }
+    }
+}
+
+enum CampaignLayout {
+    V1(CrowdfundingCampaignV1),
+}
+
+impl Layout for CampaignLayout {
+    type Current = CrowdfundingCampaign;
+
+    fn step(self) -> ControlFlow<CrowdfundingCampaign, Self> {
+        match self {
+            Self::V1(v1) => ControlFlow::Break(v1.into()),
+        }
+    }
+}
+
+// Grants issued before the count existed are not known here, so the count
+// starts at zero; `erase_personal_data` still closes any grant passed to it.
+impl From<FarmerProfileV1> for FarmerProfile {
+    fn from(v1: FarmerProfileV1) -> Self {
+        Self {
+            version: FARMER_PROFILE_VERSION,
+            farmer: v1.farmer,
+            encrypted_data: v1.encrypted_data,
+            public_name: v1.public_name,
+            region: v1.region,
+            certifications: v1.certifications,
+            verification_status: v1.verification_status,
+            reputation_score: v1.reputation_score,
+            total_products: v1.total_products,
+            created_at: v1.created_at,
+            updated_at: v1.updated_at,
+            // Which older products are still open is unknown, so assume all of
+            // them; campaigns never touched the profile before this version.
+            active_products: v1.total_products,
+            active_campaigns: 0,
+            // Free-text regions cannot be mapped reliably; the farmer sets these
+            // with `set_farm_location`.
+            region_code: String::new(),
+            geohash: None,
+            anonymised: false,
+            active_grants: 0,
+        }
+    }
+}
+
+impl From<ProductCycleV1> for ProductCycle {
+    fn from(v1: ProductCycleV1) -> Self {
+        let growth_updates: Vec<GrowthUpdate> = v1
+            .growth_updates
+            .into_iter()
+            .map(GrowthUpdate::from)
+            .collect();
+        // Older entries were posted under the farmer's key, or by staff on
+        // their behalf, so they are attributed to the farmer
+        let delivery_updates: Vec<DeliveryUpdate> = v1
+            .delivery_updates
+            .into_iter()
+            .map(|update| DeliveryUpdate {
+                status: update.status,
+                timestamp: update.timestamp,
+                notes: update.notes,
+                location: update.location,
+                checkpoint: None,
+                signed_by: v1.farmer,
+            })
+            .collect();
+        // Entries that predate the chain are folded in as stored, growth
+        // updates first, so a verifier can rebuild them from the account
+        let history_hash = growth_updates
+            .iter()
+            .cloned()
+            .map(HistoryEntry::Growth)
+            .chain(delivery_updates.iter().cloned().map(HistoryEntry::Delivery))
+            .fold(history_genesis(&v1.product_id), |prev, entry| entry.chain(&prev));
+        Self {
+            version: PRODUCT_CYCLE_VERSION,
+            product_id: v1.product_id,
+            farmer: v1.farmer,
+            product_name: v1.product_name,
+            category: legacy_category(&v1.category),
+            subcategory: None,
+            description: v1.description,
+            estimated_harvest_date: v1.estimated_harvest_date,
+            estimated_quantity: v1.estimated_quantity,
+            actual_quantity: v1.actual_quantity,
+            firebase_image_urls: v1.firebase_image_urls,
+            growth_updates,
+            delivery_updates,
+            created_at: v1.created_at,
+            updated_at: v1.updated_at,
+            price_per_unit: 0,
+            price_mint: None,
+            unit: UnitOfMeasure::Kilogram,
+            available_stock: 0,
+            min_order_quantity: 1,
+            // Existing URLs were never hashed, so they stay in the legacy field
+            media: Vec::new(),
+            history_hash,
+            parents: Vec::new(),
+            lot_status: LotStatus::Active,
+            share_campaigns: 0,
+            active_subscriptions: 0,
+            // Products were always created by, and paid for by, the farmer
+            rent_payer: v1.farmer,
+        }
+    }
+}
+
+impl From<GrowthUpdateV1> for GrowthUpdate {
+    fn from(v1: GrowthUpdateV1) -> Self {
+        Self {
+            stage: v1.stage,
+            timestamp: v1.timestamp,
+            notes: v1.notes,
+            firebase_image_urls: v1.firebase_image_urls,
+            media: Vec::new(),
+        }
+    }
+}
+
+impl From<CrowdfundingCampaignV1> for CrowdfundingCampaign {
+    fn from(v1: CrowdfundingCampaignV1) -> Self {
+        Self {
+            version: CAMPAIGN_VERSION,
+            campaign_id: v1.campaign_id,
+            farmer: v1.farmer,
+            title: v1.title,
+            description: v1.description,
+            goal_amount: v1.goal_amount,
+            current_amount: v1.current_amount,
+            deadline: v1.deadline,
+            campaign_type: v1.campaign_type,
+            milestones: v1.milestones,
+            contributors: v1.contributors,
+            is_active: v1.is_active,
+            created_at: v1.created_at,
+            updated_at: v1.updated_at,
+            is_cancelled: false,
+            deadline_extended: false,
+            total_withdrawn: 0,
+            beneficiaries: vec![Beneficiary {
+                wallet: v1.farmer,
+                share_bps: BPS_DENOMINATOR as u16,
+            }],
+            allow_overfunding: false,
+            hard_cap: None,
+            stretch_goals: Vec::new(),
+            stretch_goals_reached: 0,
+        }
+    }
+}
+
+// Legacy data has no version byte, so a layout is only accepted when it parses
+// cleanly *and* names the signing farmer as its owner.
+pub fn upgrade_farmer_profile(data: &[u8], farmer: &Pubkey) -> Result<FarmerProfile> {
+    let body = account_body::<FarmerProfile>(data)?;
+
+    if let Ok(current) = FarmerProfile::deserialize(&mut &body[..]) {
+        if current.version == FARMER_PROFILE_VERSION && current.farmer == *farmer {
+            return err!(ErrorCode::AccountAlreadyMigrated);
+        }
+    }
+
+    let legacy = FarmerProfileV1::deserialize(&mut &body[..])
+        .map_err(|_| error!(ErrorCode::UnknownAccountLayout))?;
+    require_keys_eq!(legacy.farmer, *farmer, ErrorCode::UnauthorizedFarmer);
+
+    Ok(upgrade(FarmerProfileLayout::V1(legacy)))
+}
+
+pub fn upgrade_product_cycle(data: &[u8], farmer: &Pubkey) -> Result<ProductCycle> {
+    let body = account_body::<ProductCycle>(data)?;
+
+    if let Ok(current) = ProductCycle::deserialize(&mut &body[..]) {
+        if current.version == PRODUCT_CYCLE_VERSION && current.farmer == *farmer {
+            return err!(ErrorCode::AccountAlreadyMigrated);
+        }
+    }
+
+    let legacy = ProductCycleV1::deserialize(&mut &body[..])
+        .map_err(|_| error!(ErrorCode::UnknownAccountLayout))?;
+    require_keys_eq!(legacy.farmer, *farmer, ErrorCode::UnauthorizedFarmer);
+
+    Ok(upgrade(ProductCycleLayout::V1(legacy)))
+}
+
+pub fn upgrade_campaign(data: &[u8], farmer: &Pubkey) -> Result<CrowdfundingCampaign> {
+    let body = account_body::<CrowdfundingCampaign>(data)?;
+
+    if let Ok(current) = CrowdfundingCampaign::deserialize(&mut &body[..]) {
+        if current.version == CAMPAIGN_VERSION && current.farmer == *farmer {
+            return err!(ErrorCode::AccountAlreadyMigrated);
+        }
+    }
+
+    let legacy = CrowdfundingCampaignV1::deserialize(&mut &body[..])
+        .map_err(|_| error!(ErrorCode::UnknownAccountLayout))?;
+    require_keys_eq!(legacy.farmer, *farmer, ErrorCode::UnauthorizedFarmer);
+
+    Ok(upgrade(CampaignLayout::V1(legacy)))
+}
+
+/// Grows `account` to `space` (topping up rent from `payer`) and overwrites it
+/// with the upgraded layout.
+pub fn write_upgraded<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    upgraded: &T,
+) -> Result<()> {
+    if account.data_len() < space {
+        let rent_due = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(account.lamports());
+        if rent_due > 0 {
+            let ix = system_program::Transfer {
+                from: payer.to_account_info(),
+                to: account.clone(),
+            };
+            let cpi_ctx = CpiContext::new(system_program.to_account_info(), ix);
+            system_program::transfer(cpi_ctx, rent_due)?;
+        }
+        account.resize(space)?;
+    }
+
+    let mut data = account.try_borrow_mut_data()?;
+    upgraded.try_serialize(&mut &mut data[..])
+}
+
+fn account_body<T: Discriminator>(data: &[u8]) -> Result<&[u8]> {
+    let discriminator = T::DISCRIMINATOR;
+    require!(
+        data.starts_with(discriminator),
+        anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+    );
+    Ok(&data[discriminator.len()..])
+}
+
+// Maps the free-text categories clients used to write, in Polish and English
+fn legacy_category(category: &str) -> ProductCategory {
+    match category.trim().to_lowercase().as_str() {
+        "warzywa" | "vegetables" | "vegetable" | "veg" => ProductCategory::Vegetables,
+        "owoce" | "fruits" | "fruit" => ProductCategory::Fruits,
+        "nabiał" | "nabial" | "dairy" => ProductCategory::Dairy,
+        "mięso" | "mieso" | "meat" => ProductCategory::Meat,
+        "jaja" | "jajka" | "eggs" => ProductCategory::Eggs,
+        "zboża" | "zboza" | "grains" | "cereals" => ProductCategory::Grains,
+        "miód" | "miod" | "honey" => ProductCategory::Honey,
+        "zioła" | "ziola" | "herbs" => ProductCategory::Herbs,
+        "przetwory" | "preserves" => ProductCategory::Preserves,
+        _ => ProductCategory::Other,
+    }
+}
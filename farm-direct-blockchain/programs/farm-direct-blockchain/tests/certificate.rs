@@ -0,0 +1,55 @@
+use anchor_lang::prelude::Pubkey;
+use farm_direct_blockchain::{DeliveryStatus, GrowthStage, GrowthUpdate};
+
+mod common;
+
+use common::{delivery, farmer, product};
+
+fn growth(stage: GrowthStage, timestamp: i64) -> GrowthUpdate {
+    GrowthUpdate {
+        stage,
+        timestamp,
+        notes: String::new(),
+        firebase_image_urls: Vec::new(),
+        media: Vec::new(),
+    }
+}
+
+fn field<'a>(metadata: &'a [(String, String)], key: &str) -> &'a str {
+    &metadata.iter().find(|(k, _)| k == key).unwrap().1
+}
+
+#[test]
+fn certificate_points_back_at_the_product() {
+    let address = Pubkey::new_from_array([5; 32]);
+    let metadata = product().certificate_metadata(&address);
+
+    assert_eq!(field(&metadata, "product"), address.to_string());
+    assert_eq!(field(&metadata, "farmer"), farmer().to_string());
+    assert_eq!(field(&metadata, "quantity"), "480");
+}
+
+#[test]
+fn certificate_lists_stages_and_deliveries_in_order() {
+    let mut product = product();
+    product.growth_updates = vec![
+        growth(GrowthStage::Seeding, 1_711_000_000),
+        growth(GrowthStage::Harvest, 1_721_000_000),
+    ];
+    product.delivery_updates = vec![delivery(DeliveryStatus::Delivered, farmer())];
+    let metadata = product.certificate_metadata(&Pubkey::new_from_array([5; 32]));
+
+    assert_eq!(
+        field(&metadata, "stages"),
+        "Seeding:1711000000,Harvest:1721000000"
+    );
+    assert_eq!(field(&metadata, "deliveries"), "Delivered:1723000000");
+}
+
+#[test]
+fn certificate_of_a_bare_product_has_empty_history_fields() {
+    let metadata = product().certificate_metadata(&Pubkey::new_from_array([5; 32]));
+
+    assert_eq!(field(&metadata, "stages"), "");
+    assert_eq!(field(&metadata, "deliveries"), "");
+}
@@ -0,0 +1,56 @@
+use farm_direct_blockchain::ErrorCode;
+
+mod common;
+
+use common::{pre_order_listing, product};
+
+#[test]
+fn product_without_dependents_closes() {
+    assert_eq!(product().check_closable(false, false, None), Ok(()));
+    // A settled pre-order is closed along with the product
+    assert_eq!(product().check_closable(false, false, Some(&pre_order_listing(0))), Ok(()));
+}
+
+#[test]
+fn open_reservations_keep_the_product() {
+    assert_eq!(
+        product().check_closable(false, false, Some(&pre_order_listing(2))),
+        Err(ErrorCode::PreOrderUnsettled.into())
+    );
+}
+
+#[test]
+fn courier_must_be_released_first() {
+    assert_eq!(
+        product().check_closable(false, true, None),
+        Err(ErrorCode::ProductHasCourier.into())
+    );
+}
+
+#[test]
+fn certificates_and_harvest_shares_keep_the_product_for_good() {
+    assert_eq!(
+        product().check_closable(true, false, None),
+        Err(ErrorCode::ProductHasCertificate.into())
+    );
+
+    let mut product = product();
+    product.share_campaigns = 1;
+    assert_eq!(
+        product.check_closable(false, false, None),
+        Err(ErrorCode::ProductHasHarvestShares.into())
+    );
+}
+
+#[test]
+fn active_subscriptions_keep_the_product() {
+    let mut product = product();
+    product.active_subscriptions = 1;
+    assert_eq!(
+        product.check_closable(false, false, None),
+        Err(ErrorCode::ProductHasSubscriptions.into())
+    );
+
+    product.active_subscriptions = 0;
+    assert_eq!(product.check_closable(false, false, None), Ok(()));
+}
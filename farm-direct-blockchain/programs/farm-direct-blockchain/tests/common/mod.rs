@@ -0,0 +1,174 @@
+// Accounts built in their current layout, for tests that don't exercise
+// migrations.
+#![allow(dead_code)]
+
+use anchor_lang::prelude::*;
+use farm_direct_blockchain::{
+    history_genesis, Beneficiary, CampaignType, Contributor, CrowdfundingCampaign, DeliveryStatus,
+    DeliveryUpdate, FarmerProfile, HarvestShares, LotStatus, PreOrderListing, ProductCategory, ProductCycle,
+    Subscription, UnitOfMeasure, CAMPAIGN_VERSION, FARMER_PROFILE_VERSION, PRODUCT_CYCLE_VERSION,
+};
+
+// Serializes into an account of `space` bytes, panicking if it doesn't fit
+pub fn serialize<T: AccountSerialize>(account: &T, space: usize) -> Vec<u8> {
+    let mut data = vec![0; space];
+    account.try_serialize(&mut &mut data[..]).unwrap();
+    data
+}
+
+pub fn farmer() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+pub fn profile() -> FarmerProfile {
+    FarmerProfile {
+        version: FARMER_PROFILE_VERSION,
+        farmer: farmer(),
+        encrypted_data: String::from("enc:v1:9f2c"),
+        public_name: String::from("Gospodarstwo Kowalski"),
+        region: String::from("Mazowieckie"),
+        certifications: vec![String::from("BIO")],
+        verification_status: true,
+        reputation_score: 42,
+        total_products: 4,
+        created_at: 1_700_000_000,
+        updated_at: 1_710_000_000,
+        active_products: 2,
+        active_campaigns: 0,
+        region_code: String::from("PL911"),
+        geohash: Some(String::from("u3qcn")),
+        anonymised: false,
+        active_grants: 0,
+    }
+}
+
+pub fn product() -> ProductCycle {
+    let product_id = String::from("tomatoes-2024");
+    ProductCycle {
+        version: PRODUCT_CYCLE_VERSION,
+        history_hash: history_genesis(&product_id),
+        product_id,
+        farmer: farmer(),
+        product_name: String::from("Malinowe tomatoes"),
+        category: ProductCategory::Vegetables,
+        subcategory: None,
+        description: String::from("Greenhouse tomatoes"),
+        estimated_harvest_date: 1_722_000_000,
+        estimated_quantity: 500,
+        actual_quantity: 480,
+        firebase_image_urls: Vec::new(),
+        growth_updates: Vec::new(),
+        delivery_updates: Vec::new(),
+        created_at: 1_710_000_000,
+        updated_at: 1_710_000_000,
+        price_per_unit: 2_000_000,
+        price_mint: None,
+        unit: UnitOfMeasure::Kilogram,
+        available_stock: 480,
+        min_order_quantity: 1,
+        media: Vec::new(),
+        parents: Vec::new(),
+        lot_status: LotStatus::Active,
+        share_campaigns: 0,
+        active_subscriptions: 0,
+        rent_payer: farmer(),
+    }
+}
+
+pub fn pre_order_listing(open_reservations: u32) -> PreOrderListing {
+    PreOrderListing {
+        product: Pubkey::new_from_array([9; 32]),
+        farmer: farmer(),
+        price_per_unit: 2_000_000,
+        reservable_quantity: 300,
+        reserved_quantity: 120,
+        harvested_quantity: Some(480),
+        estimated_harvest_date: 1_722_000_000,
+        is_open: true,
+        created_at: 1_715_000_000,
+        updated_at: 1_723_000_000,
+        bump: 255,
+        vault_bump: 254,
+        open_reservations,
+    }
+}
+
+pub fn delivery(status: DeliveryStatus, signed_by: Pubkey) -> DeliveryUpdate {
+    DeliveryUpdate {
+        status,
+        timestamp: 1_723_000_000,
+        notes: String::new(),
+        location: None,
+        checkpoint: None,
+        signed_by,
+    }
+}
+
+pub fn backer(byte: u8) -> Pubkey {
+    Pubkey::new_from_array([byte; 32])
+}
+
+// A seeds campaign with a 3 SOL goal and a 1 SOL backer, open until its deadline
+pub fn campaign() -> CrowdfundingCampaign {
+    CrowdfundingCampaign {
+        version: CAMPAIGN_VERSION,
+        campaign_id: String::from("seeds-2024"),
+        farmer: farmer(),
+        title: String::from("Heirloom seeds"),
+        description: String::from("Seed stock for next season"),
+        goal_amount: 3_000_000_000,
+        current_amount: 1_000_000_000,
+        deadline: 1_720_000_000,
+        campaign_type: CampaignType::Seeds,
+        milestones: Vec::new(),
+        contributors: vec![Contributor {
+            wallet: backer(1),
+            amount: 1_000_000_000,
+            timestamp: 1_710_000_000,
+        }],
+        is_active: true,
+        created_at: 1_700_000_000,
+        updated_at: 1_710_000_000,
+        is_cancelled: false,
+        deadline_extended: false,
+        total_withdrawn: 0,
+        beneficiaries: vec![Beneficiary {
+            wallet: farmer(),
+            share_bps: 10_000,
+        }],
+        allow_overfunding: false,
+        hard_cap: None,
+        stretch_goals: Vec::new(),
+        stretch_goals_reached: 0,
+    }
+}
+
+pub fn subscription(total_deliveries: u32) -> Subscription {
+    Subscription {
+        farmer: farmer(),
+        buyer: backer(5),
+        product_cycle: Pubkey::new_from_array([9; 32]),
+        price_per_delivery: 50_000_000,
+        total_deliveries,
+        delivered_count: 0,
+        confirmed_count: 0,
+        is_active: true,
+        created_at: 1_720_000_000,
+        updated_at: 1_720_000_000,
+        bump: 255,
+        vault_bump: 255,
+    }
+}
+
+// Enabled for `campaign()` but not claimed yet, so the supply is still open
+pub fn harvest_shares() -> HarvestShares {
+    HarvestShares {
+        campaign: Pubkey::new_from_array([1; 32]),
+        product_cycle: Pubkey::new_from_array([2; 32]),
+        share_mint: Pubkey::new_from_array([3; 32]),
+        total_shares: 0,
+        redeemed_shares: 0,
+        redeemed_quantity: 0,
+        bump: 255,
+    }
+}
@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+use farm_direct_blockchain::{CoopMember, CoopProduct, Cooperative, ErrorCode, MemberContribution};
+
+fn member(seed: u8) -> Pubkey {
+    Pubkey::new_from_array([seed; 32])
+}
+
+fn coop_product(quantities: &[u64]) -> CoopProduct {
+    CoopProduct {
+        cooperative: Pubkey::new_unique(),
+        product: Pubkey::new_unique(),
+        contributions: quantities
+            .iter()
+            .enumerate()
+            .map(|(i, quantity)| MemberContribution {
+                farmer: member(i as u8 + 1),
+                quantity: *quantity,
+            })
+            .collect(),
+        total_paid_out: 0,
+        created_at: 1_700_000_000,
+        updated_at: 1_700_000_000,
+        bump: 255,
+        vault_bump: 254,
+    }
+}
+
+fn cooperative(members: &[u8]) -> Cooperative {
+    Cooperative {
+        admin: member(9),
+        name: "Spółdzielnia Mazowsze".to_string(),
+        members: members
+            .iter()
+            .map(|seed| CoopMember {
+                farmer: member(*seed),
+                profile: Pubkey::new_unique(),
+                joined_at: 1_700_000_000,
+                open_contributions: 0,
+            })
+            .collect(),
+        created_at: 1_700_000_000,
+        updated_at: 1_700_000_000,
+        bump: 255,
+    }
+}
+
+#[test]
+fn payouts_follow_contributed_quantities() {
+    let product = coop_product(&[300, 100, 100]);
+
+    assert_eq!(product.payouts(1_000_000), vec![600_000, 200_000, 200_000]);
+}
+
+#[test]
+fn rounding_dust_goes_to_the_first_contributor() {
+    let product = coop_product(&[1, 1, 1]);
+
+    let payouts = product.payouts(100);
+
+    assert_eq!(payouts, vec![34, 33, 33]);
+    assert_eq!(payouts.iter().sum::<u64>(), 100);
+    // Large amounts do not overflow the intermediate product
+    assert_eq!(
+        coop_product(&[u64::MAX, u64::MAX]).payouts(u64::MAX)[1],
+        u64::MAX / 2
+    );
+}
+
+#[test]
+fn every_distribution_is_split_in_proportion_and_recorded() {
+    let mut product = coop_product(&[300, 100, 100]);
+
+    assert_eq!(
+        product.record_payout(1_000_000),
+        Ok(vec![600_000, 200_000, 200_000])
+    );
+    assert_eq!(product.record_payout(5), Ok(vec![3, 1, 1]));
+    assert_eq!(product.total_paid_out, 1_000_005);
+}
+
+#[test]
+fn recorded_payouts_cannot_overflow() {
+    let mut product = coop_product(&[1]);
+    product.total_paid_out = u64::MAX;
+
+    assert_eq!(
+        product.record_payout(1),
+        Err(ErrorCode::InvalidAmount.into())
+    );
+    assert_eq!(product.total_paid_out, u64::MAX);
+}
+
+#[test]
+fn recognises_members() {
+    let cooperative = cooperative(&[1]);
+
+    assert!(cooperative.is_member(&member(1)));
+    assert!(!cooperative.is_member(&member(9)));
+}
+
+#[test]
+fn members_stay_until_their_coop_products_close() {
+    let mut cooperative = cooperative(&[1, 2, 3]);
+    let product = coop_product(&[300, 100]);
+
+    cooperative.open_contributions(&product.contributions).unwrap();
+    assert_eq!(
+        cooperative.remove_member(&member(2)),
+        Err(ErrorCode::CoopContributionsOpen.into())
+    );
+    // Members who supplied nothing can leave at any time
+    assert_eq!(cooperative.remove_member(&member(3)), Ok(()));
+
+    cooperative.close_contributions(&product.contributions);
+    assert_eq!(cooperative.remove_member(&member(2)), Ok(()));
+    assert_eq!(
+        cooperative.remove_member(&member(2)),
+        Err(ErrorCode::NotAMember.into())
+    );
+}
+
+#[test]
+fn contributions_count_once_per_coop_product() {
+    let mut cooperative = cooperative(&[1, 2]);
+    let first = coop_product(&[300, 100]);
+    let second = coop_product(&[50]);
+
+    cooperative.open_contributions(&first.contributions).unwrap();
+    cooperative.open_contributions(&second.contributions).unwrap();
+    assert_eq!(cooperative.members[0].open_contributions, 2);
+    assert_eq!(cooperative.members[1].open_contributions, 1);
+
+    cooperative.close_contributions(&first.contributions);
+    assert_eq!(
+        cooperative.remove_member(&member(1)),
+        Err(ErrorCode::CoopContributionsOpen.into())
+    );
+    assert_eq!(cooperative.remove_member(&member(2)), Ok(()));
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+use farm_direct_blockchain::{Delegate, StaffKey, StaffPermission, StaffPermissions};
+
+fn driver() -> Pubkey {
+    Pubkey::new_from_array([3; 32])
+}
+
+fn delegate() -> Delegate {
+    Delegate {
+        farmer: Pubkey::new_from_array([7; 32]),
+        staff: vec![StaffKey {
+            key: driver(),
+            permissions: StaffPermissions {
+                growth_updates: false,
+                delivery_updates: true,
+                product_creation: false,
+            },
+            expires_at: 1_700_000_000,
+        }],
+        created_at: 1_600_000_000,
+        updated_at: 1_600_000_000,
+        bump: 255,
+    }
+}
+
+#[test]
+fn allows_only_granted_permissions() {
+    let delegate = delegate();
+
+    assert!(delegate.allows(&driver(), StaffPermission::DeliveryUpdates, 1_650_000_000));
+    assert!(!delegate.allows(&driver(), StaffPermission::GrowthUpdates, 1_650_000_000));
+    assert!(!delegate.allows(&driver(), StaffPermission::ProductCreation, 1_650_000_000));
+    assert!(!delegate.allows(
+        &Pubkey::new_from_array([4; 32]),
+        StaffPermission::DeliveryUpdates,
+        1_650_000_000
+    ));
+}
+
+#[test]
+fn expired_staff_keys_are_refused() {
+    let delegate = delegate();
+
+    assert!(!delegate.allows(&driver(), StaffPermission::DeliveryUpdates, 1_700_000_000));
+}
+
+#[test]
+fn full_staff_list_fits_in_space() {
+    let mut delegate = delegate();
+    delegate.staff = vec![delegate.staff[0].clone(); farm_direct_blockchain::MAX_STAFF_KEYS];
+
+    let mut data = vec![0; Delegate::SPACE];
+    delegate.try_serialize(&mut &mut data[..]).unwrap();
+}
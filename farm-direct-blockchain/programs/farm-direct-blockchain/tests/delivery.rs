@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use farm_direct_blockchain::DeliveryStatus;
+
+mod common;
+
+use common::{delivery, farmer, product};
+
+#[test]
+fn completion_needs_a_confirmed_delivery() {
+    let courier = Pubkey::new_from_array([3; 32]);
+    let mut product = product();
+    assert!(!product.awaiting_completion());
+
+    product.delivery_updates = vec![
+        delivery(DeliveryStatus::Packed, farmer()),
+        delivery(DeliveryStatus::InTransit, courier),
+    ];
+    assert!(!product.awaiting_completion());
+
+    product.delivery_updates.push(delivery(DeliveryStatus::Delivered, courier));
+    assert!(product.awaiting_completion());
+}
+
+#[test]
+fn each_confirmation_completes_once() {
+    let buyer = Pubkey::new_from_array([5; 32]);
+    let mut product = product();
+    product.delivery_updates = vec![
+        delivery(DeliveryStatus::Delivered, buyer),
+        delivery(DeliveryStatus::Completed, farmer()),
+    ];
+    assert!(!product.awaiting_completion());
+
+    // Later updates for the next delivery don't reopen the previous one
+    product.delivery_updates.push(delivery(DeliveryStatus::InTransit, farmer()));
+    assert!(!product.awaiting_completion());
+
+    product.delivery_updates.push(delivery(DeliveryStatus::Delivered, buyer));
+    assert!(product.awaiting_completion());
+}
@@ -0,0 +1,33 @@
+use farm_direct_blockchain::envelope::{
+    is_valid_profile_data, is_valid_wrapped_key, WRAPPED_KEY_LEN,
+};
+use farm_direct_blockchain::is_valid_encrypted_data;
+
+#[test]
+fn accepts_only_v1_profile_envelopes() {
+    // 1 + 24 + 16 bytes, the smallest possible envelope
+    let minimal = format!("enc:v1:{}", "A".repeat(56));
+    assert!(is_valid_profile_data(&minimal));
+    assert!(is_valid_encrypted_data(""));
+
+    for data in [
+        "enc:v0:deadbeef",
+        "enc:v1:",
+        "enc:v1:AAA",
+        "enc:v1:AAAAAAAA",
+        "enc:v1:AA*A",
+        "plain text phone number",
+    ] {
+        assert!(!is_valid_encrypted_data(data), "{data}");
+    }
+}
+
+#[test]
+fn checks_wrapped_key_length_and_version() {
+    let mut wrapped = vec![1; WRAPPED_KEY_LEN];
+    assert!(is_valid_wrapped_key(&wrapped));
+
+    wrapped[0] = 2;
+    assert!(!is_valid_wrapped_key(&wrapped));
+    assert!(!is_valid_wrapped_key(&[1; 32]));
+}
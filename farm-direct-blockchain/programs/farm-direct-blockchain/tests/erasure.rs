@@ -0,0 +1,32 @@
+use farm_direct_blockchain::ErrorCode;
+
+mod common;
+
+use common::profile;
+
+#[test]
+fn erasure_waits_for_every_grant() {
+    let mut profile = profile();
+    profile.active_grants = 1;
+
+    assert_eq!(
+        profile.anonymise(1_723_000_000),
+        Err(ErrorCode::AccessGrantsOutstanding.into())
+    );
+    assert!(!profile.anonymised);
+    assert_eq!(profile.public_name, "Gospodarstwo Kowalski");
+}
+
+#[test]
+fn erasure_wipes_personal_fields_but_keeps_counters() {
+    let mut profile = profile();
+
+    profile.anonymise(1_723_000_000).unwrap();
+
+    assert!(profile.anonymised);
+    assert!(profile.encrypted_data.is_empty() && profile.public_name.is_empty());
+    assert!(profile.region.is_empty() && profile.region_code.is_empty());
+    assert_eq!(profile.geohash, None);
+    assert_eq!((profile.total_products, profile.reputation_score), (4, 42));
+    assert_eq!(profile.updated_at, 1_723_000_000);
+}
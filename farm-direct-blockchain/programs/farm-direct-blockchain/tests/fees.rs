@@ -0,0 +1,49 @@
+use farm_direct_blockchain::{is_valid_fee_bps, split_platform_fee, MAX_PLATFORM_FEE_BPS};
+
+mod common;
+
+use common::campaign;
+
+#[test]
+fn zero_bps_takes_no_fee() {
+    assert_eq!(split_platform_fee(1_000_000_000, 0), (0, 1_000_000_000));
+}
+
+#[test]
+fn max_bps_takes_a_tenth() {
+    assert_eq!(
+        split_platform_fee(1_000_000_000, MAX_PLATFORM_FEE_BPS),
+        (100_000_000, 900_000_000)
+    );
+}
+
+#[test]
+fn fee_rounds_down_and_leaves_dust_with_the_farmer() {
+    // 2.5% of 999 is 24.975
+    assert_eq!(split_platform_fee(999, 250), (24, 975));
+    assert_eq!(split_platform_fee(39, 250), (0, 39));
+}
+
+#[test]
+fn fee_does_not_overflow_on_large_amounts() {
+    let (fee, net) = split_platform_fee(u64::MAX, MAX_PLATFORM_FEE_BPS);
+
+    assert_eq!(fee, u64::MAX / 10);
+    assert_eq!(fee + net, u64::MAX);
+}
+
+#[test]
+fn fee_bps_are_capped() {
+    assert!(is_valid_fee_bps(0));
+    assert!(is_valid_fee_bps(MAX_PLATFORM_FEE_BPS));
+    assert!(!is_valid_fee_bps(MAX_PLATFORM_FEE_BPS + 1));
+}
+
+#[test]
+fn campaign_withdrawal_pays_beneficiaries_net_of_the_fee() {
+    let campaign = campaign();
+    let (fee, net) = split_platform_fee(3_000_000_001, 250);
+
+    assert_eq!(fee, 75_000_000);
+    assert_eq!(campaign.payouts(net), vec![2_925_000_001]);
+}
@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use farm_direct_blockchain::{ErrorCode, HarvestShares};
+
+mod common;
+
+use common::{backer, campaign, harvest_shares, serialize};
+
+const AFTER_DEADLINE: i64 = 1_720_000_001;
+
+#[test]
+fn supply_is_fixed_at_the_first_claim() {
+    let mut shares = harvest_shares();
+
+    shares.snapshot_total(3_000_000_000);
+    // A refund afterwards lowers the campaign's total, not the supply
+    shares.snapshot_total(2_000_000_000);
+
+    assert_eq!(shares.total_shares, 3_000_000_000);
+    assert_eq!(shares.quantity_for(1_000_000_000, 480), 160);
+}
+
+#[test]
+fn redemptions_round_down_and_never_exceed_the_harvest() {
+    let mut shares = harvest_shares();
+    shares.snapshot_total(3);
+
+    let redeemed: Vec<u64> = (0..3).map(|_| shares.quantity_for(1, 100)).collect();
+
+    assert_eq!(redeemed, vec![33, 33, 33]);
+    assert_eq!(shares.quantity_for(3, 100), 100);
+}
+
+#[test]
+fn large_supplies_do_not_overflow() {
+    let mut shares = harvest_shares();
+    shares.snapshot_total(u64::MAX);
+
+    assert_eq!(shares.quantity_for(u64::MAX, u64::MAX), u64::MAX);
+    assert_eq!(shares.quantity_for(u64::MAX / 2, 1_000), 499);
+}
+
+#[test]
+fn nothing_is_redeemable_before_a_claim() {
+    assert_eq!(harvest_shares().quantity_for(10, 480), 0);
+}
+
+#[test]
+fn unclaimed_shares_leave_the_campaign_cancellable_and_refundable() {
+    let mut campaign = campaign();
+    campaign.current_amount = campaign.goal_amount;
+    let shares = harvest_shares();
+
+    assert_eq!(campaign.check_cancellable(Some(&shares)), Ok(()));
+    campaign.is_cancelled = true;
+    assert_eq!(
+        campaign.refund_for(&backer(1), AFTER_DEADLINE, false),
+        Ok(1_000_000_000)
+    );
+}
+
+#[test]
+fn a_claim_rules_out_cancelling_the_campaign() {
+    let mut campaign = campaign();
+    campaign.current_amount = campaign.goal_amount;
+    let mut shares = harvest_shares();
+
+    shares.snapshot_total(campaign.current_amount);
+
+    // A claimed backer could never be refunded, so the campaign stays funded
+    assert_eq!(
+        campaign.check_cancellable(Some(&shares)),
+        Err(ErrorCode::SharesAlreadyClaimed.into())
+    );
+    assert_eq!(
+        campaign.refund_for(&backer(1), AFTER_DEADLINE, true),
+        Err(ErrorCode::CampaignNotRefundable.into())
+    );
+}
+
+#[test]
+fn fits_in_space() {
+    let mut shares = harvest_shares();
+    shares.snapshot_total(u64::MAX);
+
+    let data = serialize(&shares, HarvestShares::SPACE);
+    let reloaded = HarvestShares::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(reloaded.total_shares, u64::MAX);
+}
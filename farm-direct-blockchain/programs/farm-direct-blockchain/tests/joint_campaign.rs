@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use farm_direct_blockchain::{is_valid_beneficiaries, Beneficiary, MAX_CAMPAIGN_BENEFICIARIES};
+
+mod common;
+
+use common::campaign;
+
+fn share(seed: u8, share_bps: u16) -> Beneficiary {
+    Beneficiary {
+        wallet: Pubkey::new_from_array([seed; 32]),
+        share_bps,
+    }
+}
+
+#[test]
+fn shares_must_sum_to_the_whole() {
+    assert!(is_valid_beneficiaries(&[share(1, 10_000)]));
+    assert!(is_valid_beneficiaries(&[
+        share(1, 5_000),
+        share(2, 3_000),
+        share(3, 2_000)
+    ]));
+
+    assert!(!is_valid_beneficiaries(&[]));
+    assert!(!is_valid_beneficiaries(&[share(1, 5_000), share(2, 4_999)]));
+    assert!(!is_valid_beneficiaries(&[share(1, 5_000), share(2, 5_001)]));
+    assert!(!is_valid_beneficiaries(&[share(1, 9_999)]));
+    // Shares are summed wide, so u16 wrap-around cannot fake a whole
+    assert!(!is_valid_beneficiaries(&[
+        share(1, u16::MAX),
+        share(2, u16::MAX),
+        share(3, 10_002)
+    ]));
+    assert!(!is_valid_beneficiaries(&[share(1, 10_000), share(2, 0)]));
+    assert!(!is_valid_beneficiaries(&[share(1, 5_000), share(1, 5_000)]));
+    let too_many: Vec<_> = (0..=MAX_CAMPAIGN_BENEFICIARIES as u8)
+        .map(|seed| share(seed, 1))
+        .collect();
+    assert!(!is_valid_beneficiaries(&too_many));
+}
+
+#[test]
+fn withdrawal_is_split_by_share() {
+    let mut campaign = campaign();
+    campaign.beneficiaries = vec![share(1, 5_000), share(2, 3_333), share(3, 1_667)];
+
+    let payouts = campaign.payouts(1_000_001);
+
+    assert_eq!(payouts, vec![500_001, 333_300, 166_700]);
+    assert_eq!(payouts.iter().sum::<u64>(), 1_000_001);
+}
+
+#[test]
+fn tiny_withdrawal_dust_goes_to_the_first_beneficiary() {
+    let mut campaign = campaign();
+    campaign.beneficiaries = vec![share(1, 3_334), share(2, 3_333), share(3, 3_333)];
+
+    assert_eq!(campaign.payouts(2), vec![2, 0, 0]);
+    assert_eq!(campaign.payouts(0), vec![0, 0, 0]);
+}
@@ -0,0 +1,30 @@
+use farm_direct_blockchain::{
+    is_valid_geohash, is_valid_region_code, MAX_CHECKPOINT_GEOHASH_LEN, MAX_FARM_GEOHASH_LEN,
+};
+
+#[test]
+fn accepts_nuts_and_teryt_region_codes() {
+    for code in ["PL", "PL9", "PL91", "PL911", "14", "1465", "146501", "1465011"] {
+        assert!(is_valid_region_code(code), "{code}");
+    }
+    for code in ["", "pl911", "P", "PL9111", "146", "14650111", "Mazowieckie"] {
+        assert!(!is_valid_region_code(code), "{code}");
+    }
+}
+
+#[test]
+fn caps_farm_geohash_precision() {
+    assert!(is_valid_geohash("u3qcn", MAX_FARM_GEOHASH_LEN));
+    assert!(is_valid_geohash("u3qcnh", MAX_FARM_GEOHASH_LEN));
+    assert!(!is_valid_geohash("u3qcnhx", MAX_FARM_GEOHASH_LEN));
+    assert!(is_valid_geohash("u3qcnhxpwz7k", MAX_CHECKPOINT_GEOHASH_LEN));
+    assert!(!is_valid_geohash("u3qcnhxpwz7kb", MAX_CHECKPOINT_GEOHASH_LEN));
+}
+
+#[test]
+fn rejects_characters_outside_the_geohash_alphabet() {
+    assert!(!is_valid_geohash("", MAX_CHECKPOINT_GEOHASH_LEN));
+    for geohash in ["u3qa", "u3qi", "u3ql", "u3qo", "U3QC"] {
+        assert!(!is_valid_geohash(geohash, MAX_CHECKPOINT_GEOHASH_LEN), "{geohash}");
+    }
+}
@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+use farm_direct_blockchain::{
+    history_genesis, is_valid_split, rent_shortfall, ErrorCode, HistoryEntry, LotParent,
+    LotStatus, ProductCycle, MAX_SPLIT_CHILDREN,
+};
+
+mod common;
+
+use common::{pre_order_listing, product};
+
+#[test]
+fn split_must_cover_the_whole_harvest() {
+    assert!(is_valid_split(&[300, 180], 480));
+    assert!(is_valid_split(&[60; MAX_SPLIT_CHILDREN], 480));
+
+    assert!(!is_valid_split(&[300, 100], 480));
+    assert!(!is_valid_split(&[480, 0], 480));
+    assert!(!is_valid_split(&[480], 480));
+    assert!(!is_valid_split(&[u64::MAX, 481], 480));
+    assert!(!is_valid_split(&[40; MAX_SPLIT_CHILDREN + 4], 480));
+}
+
+#[test]
+fn lot_inherits_the_product_but_not_its_logs() {
+    let parent = product();
+    let address = Pubkey::new_unique();
+    let coop = Pubkey::new_unique();
+
+    let lot = ProductCycle::new_lot(&parent, address, 3, coop, 1_723_000_000);
+
+    assert_eq!(lot.farmer, coop);
+    // Whoever splits or merges pays for the lot
+    assert_eq!(lot.rent_payer, coop);
+    assert_eq!(lot.product_name, parent.product_name);
+    assert_eq!(lot.category, parent.category);
+    assert_ne!(lot.product_id, parent.product_id);
+    assert!(lot.growth_updates.is_empty() && lot.delivery_updates.is_empty());
+    assert_eq!(lot.history_hash, history_genesis(&lot.product_id));
+    assert_eq!((lot.price_per_unit, lot.available_stock), (0, 0));
+    assert_eq!(lot.lot_status, LotStatus::Active);
+
+    // Lots at different addresses start different chains
+    let sibling = ProductCycle::new_lot(&parent, Pubkey::new_unique(), 4, coop, 1_723_000_000);
+    assert_ne!(sibling.history_hash, lot.history_hash);
+}
+
+#[test]
+fn lot_history_pins_its_parents() {
+    let mut parent = product();
+    // The parent has logged entries since its genesis
+    parent.history_hash = [3; 32];
+    let lot = ProductCycle::new_lot(
+        &parent,
+        Pubkey::new_unique(),
+        3,
+        parent.farmer,
+        1_723_000_000,
+    );
+    let origin = |history_hash| HistoryEntry::Lot {
+        parents: vec![LotParent {
+            product: Pubkey::new_from_array([9; 32]),
+            history_hash,
+            quantity: 300,
+        }],
+        timestamp: 1_723_000_000,
+    };
+
+    let recorded = origin(parent.history_hash).chain(&lot.history_hash);
+    let forged = origin(history_genesis(&parent.product_id)).chain(&lot.history_hash);
+
+    assert_ne!(recorded, forged);
+}
+
+#[test]
+fn pre_funded_lot_address_is_only_topped_up() {
+    let rent = Rent::default();
+    let minimum = rent.minimum_balance(ProductCycle::SPACE);
+
+    assert_eq!(rent_shortfall(&rent, 0, ProductCycle::SPACE), minimum);
+    assert_eq!(rent_shortfall(&rent, 1, ProductCycle::SPACE), minimum - 1);
+    assert_eq!(rent_shortfall(&rent, minimum, ProductCycle::SPACE), 0);
+    assert_eq!(rent_shortfall(&rent, minimum + 1, ProductCycle::SPACE), 0);
+}
+
+#[test]
+fn lots_owing_nothing_can_be_divided() {
+    assert_eq!(product().check_divisible(None), Ok(()));
+    assert_eq!(product().check_divisible(Some(&pre_order_listing(0))), Ok(()));
+
+    let mut split = product();
+    split.lot_status = LotStatus::Split;
+    assert_eq!(
+        split.check_divisible(None),
+        Err(ErrorCode::LotNotActive.into())
+    );
+}
+
+#[test]
+fn reservations_shares_and_subscriptions_stay_with_the_lot() {
+    assert_eq!(
+        product().check_divisible(Some(&pre_order_listing(1))),
+        Err(ErrorCode::PreOrderUnsettled.into())
+    );
+
+    let mut product = product();
+    product.share_campaigns = 1;
+    assert_eq!(
+        product.check_divisible(None),
+        Err(ErrorCode::ProductHasHarvestShares.into())
+    );
+
+    product.share_campaigns = 0;
+    product.active_subscriptions = 1;
+    assert_eq!(
+        product.check_divisible(None),
+        Err(ErrorCode::ProductHasSubscriptions.into())
+    );
+}
+
+#[test]
+fn division_ends_the_parent_chain() {
+    let parent = product();
+    let lot = Pubkey::new_unique();
+    let split = HistoryEntry::Split {
+        lots: vec![lot],
+        timestamp: 1_723_000_000,
+    };
+    let merged = HistoryEntry::Merged {
+        lot,
+        timestamp: 1_723_000_000,
+    };
+
+    // The lot pins the hash before the closing entry, which still commits to it
+    assert_ne!(split.chain(&parent.history_hash), parent.history_hash);
+    assert_ne!(
+        split.chain(&parent.history_hash),
+        merged.chain(&parent.history_hash)
+    );
+}
@@ -0,0 +1,39 @@
+use farm_direct_blockchain::{is_valid_media, MediaRef, MAX_MEDIA_PER_ENTRY};
+
+fn media(uri: &str, mime: &str) -> MediaRef {
+    MediaRef {
+        uri: uri.to_string(),
+        sha256: [9; 32],
+        mime: mime.to_string(),
+    }
+}
+
+#[test]
+fn accepts_ipfs_arweave_and_firebase_media() {
+    assert!(is_valid_media(&[
+        media("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi", "image/jpeg"),
+        media("ar://bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt_U", "image/webp"),
+        media(
+            "https://firebasestorage.googleapis.com/v0/b/farm/o/tomatoes.png",
+            "image/png"
+        ),
+    ]));
+    assert!(is_valid_media(&[]));
+}
+
+#[test]
+fn rejects_invalid_media() {
+    assert!(!is_valid_media(&[media("https://example.com/a.jpg", "image/jpeg")]));
+    assert!(!is_valid_media(&[media("ipfs://", "image/jpeg")]));
+    assert!(!is_valid_media(&[media("ipfs://bafy", "application/pdf")]));
+
+    let mut unhashed = media("ipfs://bafy", "image/jpeg");
+    unhashed.sha256 = [0; 32];
+    assert!(!is_valid_media(&[unhashed]));
+
+    let too_long = format!("ipfs://{}", "a".repeat(128));
+    assert!(!is_valid_media(&[media(&too_long, "image/jpeg")]));
+
+    let too_many = vec![media("ipfs://bafy", "image/jpeg"); MAX_MEDIA_PER_ENTRY + 1];
+    assert!(!is_valid_media(&too_many));
+}
@@ -0,0 +1,192 @@
+use anchor_lang::prelude::*;
+use farm_direct_blockchain::migration::{
+    upgrade_campaign, upgrade_farmer_profile, upgrade_product_cycle,
+};
+use farm_direct_blockchain::{
+    history_genesis, CampaignType, CrowdfundingCampaign, DeliveryStatus, ErrorCode,
+    FarmerProfile, GrowthStage, HistoryEntry, LotStatus, ProductCategory, ProductCycle,
+    UnitOfMeasure, CAMPAIGN_VERSION, FARMER_PROFILE_VERSION, PRODUCT_CYCLE_VERSION,
+};
+
+mod common;
+
+use common::serialize;
+
+// Account dumps written with the original, unversioned layouts.
+const FARMER_PROFILE_V1: &[u8] = include_bytes!("fixtures/farmer_profile_v1.bin");
+const PRODUCT_CYCLE_V1: &[u8] = include_bytes!("fixtures/product_cycle_v1.bin");
+const CAMPAIGN_V1: &[u8] = include_bytes!("fixtures/campaign_v1.bin");
+
+fn fixture_farmer() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+#[test]
+fn migrates_v1_farmer_profile() {
+    let profile = upgrade_farmer_profile(FARMER_PROFILE_V1, &fixture_farmer()).unwrap();
+
+    assert_eq!(profile.version, FARMER_PROFILE_VERSION);
+    assert_eq!(profile.farmer, fixture_farmer());
+    assert_eq!(profile.encrypted_data, "enc:v0:deadbeef");
+    assert_eq!(profile.public_name, "Gospodarstwo Kowalski");
+    assert_eq!(profile.region, "mazowieckie");
+    assert_eq!(profile.certifications, vec!["BIO", "Lokalne"]);
+    assert!(profile.verification_status);
+    assert_eq!(profile.reputation_score, 42);
+    assert_eq!(profile.total_products, 3);
+    assert_eq!(profile.created_at, 1_700_000_000);
+    assert_eq!(profile.updated_at, 1_700_000_500);
+    assert_eq!(profile.active_products, 3);
+    assert_eq!(profile.active_campaigns, 0);
+    assert_eq!(profile.region_code, "");
+    assert_eq!(profile.geohash, None);
+    assert!(!profile.anonymised);
+    assert_eq!(profile.active_grants, 0);
+
+    let data = serialize(&profile, FarmerProfile::SPACE);
+    let reloaded = FarmerProfile::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(reloaded.public_name, profile.public_name);
+}
+
+#[test]
+fn migrates_v1_product_cycle() {
+    let product = upgrade_product_cycle(PRODUCT_CYCLE_V1, &fixture_farmer()).unwrap();
+
+    assert_eq!(product.version, PRODUCT_CYCLE_VERSION);
+    assert_eq!(product.farmer, fixture_farmer());
+    assert_eq!(product.product_name, "Pomidory malinowe");
+    assert_eq!(product.category, ProductCategory::Vegetables);
+    assert_eq!(product.subcategory, None);
+    assert_eq!(product.estimated_quantity, 500);
+    assert_eq!(product.firebase_image_urls.len(), 1);
+    assert_eq!(product.growth_updates.len(), 2);
+    assert_eq!(product.growth_updates[1].stage, GrowthStage::Growing);
+    assert!(product.growth_updates[1].media.is_empty());
+    assert!(product.media.is_empty());
+    assert_eq!(product.delivery_updates.len(), 2);
+    assert_eq!(
+        product.delivery_updates[1].status,
+        DeliveryStatus::InTransit
+    );
+    assert_eq!(
+        product.delivery_updates[1].location.as_deref(),
+        Some("Warszawa")
+    );
+    assert_eq!(product.delivery_updates[1].checkpoint, None);
+    assert!(product
+        .delivery_updates
+        .iter()
+        .all(|update| update.signed_by == fixture_farmer()));
+    assert_eq!(product.price_per_unit, 0);
+    assert_eq!(product.price_mint, None);
+    assert_eq!(product.unit, UnitOfMeasure::Kilogram);
+    assert_eq!(product.available_stock, 0);
+    assert_eq!(product.min_order_quantity, 1);
+    assert!(product.parents.is_empty());
+    assert_eq!(product.lot_status, LotStatus::Active);
+    assert_eq!(product.share_campaigns, 0);
+    assert_eq!(product.active_subscriptions, 0);
+    assert_eq!(product.rent_payer, fixture_farmer());
+
+    // Stored entries seed the chain, growth updates first
+    let seeded = product
+        .growth_updates
+        .iter()
+        .cloned()
+        .map(HistoryEntry::Growth)
+        .chain(product.delivery_updates.iter().cloned().map(HistoryEntry::Delivery))
+        .fold(history_genesis(&product.product_id), |prev, entry| entry.chain(&prev));
+    assert_eq!(product.history_hash, seeded);
+    assert_ne!(product.history_hash, history_genesis(&product.product_id));
+
+    let data = serialize(&product, ProductCycle::SPACE);
+    ProductCycle::try_deserialize(&mut &data[..]).unwrap();
+}
+
+#[test]
+fn migrates_v1_campaign() {
+    let campaign = upgrade_campaign(CAMPAIGN_V1, &fixture_farmer()).unwrap();
+
+    assert_eq!(campaign.version, CAMPAIGN_VERSION);
+    assert_eq!(campaign.title, "New greenhouse");
+    assert_eq!(campaign.goal_amount, 10_000_000_000);
+    assert_eq!(campaign.current_amount, 3_000_000_000);
+    assert_eq!(campaign.campaign_type, CampaignType::Infrastructure);
+    assert_eq!(campaign.milestones, vec!["Frame", "Glazing"]);
+    assert_eq!(campaign.contributors.len(), 2);
+    assert_eq!(campaign.contributors[1].amount, 2_000_000_000);
+    assert!(campaign.is_active);
+    assert!(!campaign.is_cancelled);
+    assert!(!campaign.deadline_extended);
+    assert_eq!(campaign.total_withdrawn, 0);
+    // Existing campaigns pay the farmer alone and keep closing at their goal
+    assert_eq!(campaign.beneficiaries.len(), 1);
+    assert_eq!(campaign.beneficiaries[0].wallet, fixture_farmer());
+    assert_eq!(campaign.payouts(1_000), vec![1_000]);
+    assert!(!campaign.allow_overfunding);
+    assert_eq!(campaign.hard_cap, None);
+    assert!(campaign.stretch_goals.is_empty());
+
+    let data = serialize(&campaign, CrowdfundingCampaign::SPACE);
+    CrowdfundingCampaign::try_deserialize(&mut &data[..]).unwrap();
+}
+
+#[test]
+fn rejects_migration_by_another_wallet() {
+    let stranger = Pubkey::new_from_array([1; 32]);
+
+    assert_eq!(
+        upgrade_farmer_profile(FARMER_PROFILE_V1, &stranger)
+            .err()
+            .unwrap(),
+        ErrorCode::UnauthorizedFarmer.into()
+    );
+    assert_eq!(
+        upgrade_product_cycle(PRODUCT_CYCLE_V1, &stranger)
+            .err()
+            .unwrap(),
+        ErrorCode::UnauthorizedFarmer.into()
+    );
+    assert_eq!(
+        upgrade_campaign(CAMPAIGN_V1, &stranger).err().unwrap(),
+        ErrorCode::UnauthorizedFarmer.into()
+    );
+}
+
+#[test]
+fn rejects_already_migrated_accounts() {
+    let profile = upgrade_farmer_profile(FARMER_PROFILE_V1, &fixture_farmer()).unwrap();
+    let data = serialize(&profile, FarmerProfile::SPACE);
+    assert_eq!(
+        upgrade_farmer_profile(&data, &fixture_farmer())
+            .err()
+            .unwrap(),
+        ErrorCode::AccountAlreadyMigrated.into()
+    );
+
+    let product = upgrade_product_cycle(PRODUCT_CYCLE_V1, &fixture_farmer()).unwrap();
+    let data = serialize(&product, ProductCycle::SPACE);
+    assert_eq!(
+        upgrade_product_cycle(&data, &fixture_farmer())
+            .err()
+            .unwrap(),
+        ErrorCode::AccountAlreadyMigrated.into()
+    );
+
+    let campaign = upgrade_campaign(CAMPAIGN_V1, &fixture_farmer()).unwrap();
+    let data = serialize(&campaign, CrowdfundingCampaign::SPACE);
+    assert_eq!(
+        upgrade_campaign(&data, &fixture_farmer()).err().unwrap(),
+        ErrorCode::AccountAlreadyMigrated.into()
+    );
+}
+
+#[test]
+fn rejects_accounts_of_another_type() {
+    assert_eq!(
+        upgrade_farmer_profile(PRODUCT_CYCLE_V1, &fixture_farmer())
+            .err()
+            .unwrap(),
+        anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+}
@@ -0,0 +1,81 @@
+use anchor_lang::prelude::Pubkey;
+use farm_direct_blockchain::{PausableFeature, PlatformConfig};
+
+const FEATURES: [PausableFeature; 3] = [
+    PausableFeature::Crowdfunding,
+    PausableFeature::Orders,
+    PausableFeature::ProfileWrites,
+];
+
+fn admin() -> Pubkey {
+    Pubkey::new_from_array([1; 32])
+}
+
+fn guardian() -> Pubkey {
+    Pubkey::new_from_array([2; 32])
+}
+
+fn config() -> PlatformConfig {
+    PlatformConfig {
+        admin: admin(),
+        guardian: guardian(),
+        campaign_fee_bps: 250,
+        order_fee_bps: 100,
+        paused: false,
+        crowdfunding_paused: false,
+        orders_paused: false,
+        profile_writes_paused: false,
+        created_at: 1_700_000_000,
+        updated_at: 1_700_000_000,
+        bump: 255,
+        treasury_bump: 254,
+    }
+}
+
+#[test]
+fn nothing_is_paused_by_default() {
+    let config = config();
+
+    assert!(FEATURES.iter().all(|feature| !config.is_paused(*feature)));
+}
+
+#[test]
+fn global_pause_rejects_every_feature() {
+    let mut config = config();
+    config.set_pause_flag(None, true);
+
+    assert!(FEATURES.iter().all(|feature| config.is_paused(*feature)));
+
+    config.set_pause_flag(None, false);
+    assert!(FEATURES.iter().all(|feature| !config.is_paused(*feature)));
+}
+
+#[test]
+fn feature_pause_rejects_only_that_feature() {
+    let mut config = config();
+    config.set_pause_flag(Some(PausableFeature::Orders), true);
+
+    assert!(config.is_paused(PausableFeature::Orders));
+    assert!(!config.is_paused(PausableFeature::Crowdfunding));
+    assert!(!config.is_paused(PausableFeature::ProfileWrites));
+}
+
+#[test]
+fn lifting_the_global_pause_keeps_feature_pauses() {
+    let mut config = config();
+    config.set_pause_flag(Some(PausableFeature::Crowdfunding), true);
+    config.set_pause_flag(None, true);
+    config.set_pause_flag(None, false);
+
+    assert!(config.is_paused(PausableFeature::Crowdfunding));
+    assert!(!config.is_paused(PausableFeature::Orders));
+}
+
+#[test]
+fn only_the_admin_or_guardian_can_pause() {
+    let config = config();
+
+    assert!(config.can_pause(&admin()));
+    assert!(config.can_pause(&guardian()));
+    assert!(!config.can_pause(&Pubkey::new_from_array([3; 32])));
+}
@@ -0,0 +1,113 @@
+use anchor_lang::prelude::Pubkey;
+use farm_direct_blockchain::{
+    ErrorCode, PreOrderListing, Reservation, PRE_ORDER_REFUND_GRACE_PERIOD,
+};
+
+mod common;
+
+use common::{backer, farmer};
+
+const HARVEST_DATE: i64 = 1_720_000_000;
+const PRICE: u64 = 1_000;
+
+fn listing(reserved_quantity: u64, harvested_quantity: Option<u64>) -> PreOrderListing {
+    PreOrderListing {
+        product: Pubkey::new_from_array([7; 32]),
+        farmer: farmer(),
+        price_per_unit: PRICE,
+        reservable_quantity: 100,
+        reserved_quantity,
+        harvested_quantity,
+        estimated_harvest_date: HARVEST_DATE,
+        is_open: true,
+        created_at: 1_700_000_000,
+        updated_at: 1_700_000_000,
+        bump: 255,
+        vault_bump: 254,
+        open_reservations: 1,
+    }
+}
+
+fn reservation(quantity: u64) -> Reservation {
+    Reservation {
+        listing: Pubkey::new_from_array([8; 32]),
+        buyer: backer(1),
+        quantity,
+        amount_paid: quantity * PRICE,
+        created_at: 1_710_000_000,
+        bump: 255,
+    }
+}
+
+#[test]
+fn full_harvest_pays_the_whole_reservation() {
+    let listing = listing(30, Some(50));
+
+    assert_eq!(
+        listing.settlement(&reservation(10), HARVEST_DATE),
+        Ok((10 * PRICE, 0))
+    );
+}
+
+#[test]
+fn short_harvest_fills_reservations_pro_rata() {
+    let listing = listing(40, Some(20));
+
+    assert_eq!(
+        listing.settlement(&reservation(10), HARVEST_DATE),
+        Ok((5 * PRICE, 5 * PRICE))
+    );
+}
+
+#[test]
+fn pro_rata_fill_rounds_down_in_the_buyers_favour() {
+    // 7 of 30 units fill 7 * 10 / 30 = 2.33 units of a 10 unit reservation
+    let listing = listing(30, Some(7));
+    let (payment, refund) = listing.settlement(&reservation(10), HARVEST_DATE).unwrap();
+
+    assert_eq!(payment, 2 * PRICE);
+    assert_eq!(refund, 8 * PRICE);
+    assert_eq!(payment + refund, reservation(10).amount_paid);
+}
+
+#[test]
+fn settlements_never_pay_out_more_than_the_harvest() {
+    let listing = listing(30, Some(7));
+    let paid: u64 = [10, 10, 10]
+        .iter()
+        .map(|quantity| {
+            listing
+                .settlement(&reservation(*quantity), HARVEST_DATE)
+                .unwrap()
+                .0
+        })
+        .sum();
+
+    assert!(paid <= 7 * PRICE);
+}
+
+#[test]
+fn missing_harvest_refunds_in_full_after_the_grace_period() {
+    let listing = listing(30, None);
+
+    assert_eq!(
+        listing.settlement(
+            &reservation(10),
+            HARVEST_DATE + PRE_ORDER_REFUND_GRACE_PERIOD + 1
+        ),
+        Ok((0, 10 * PRICE))
+    );
+}
+
+#[test]
+fn missing_harvest_cannot_settle_during_the_grace_period() {
+    let listing = listing(30, None);
+
+    assert_eq!(
+        listing.settlement(
+            &reservation(10),
+            HARVEST_DATE + PRE_ORDER_REFUND_GRACE_PERIOD
+        ),
+        Err(ErrorCode::HarvestNotRecorded.into())
+    );
+}
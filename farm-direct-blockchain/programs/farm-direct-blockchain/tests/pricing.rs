@@ -0,0 +1,69 @@
+use anchor_lang::prelude::Pubkey;
+use farm_direct_blockchain::{ErrorCode, UnitOfMeasure};
+
+mod common;
+
+use common::product;
+
+#[test]
+fn sets_price_unit_and_minimum_order() {
+    let mut product = product();
+    let mint = Pubkey::new_from_array([9; 32]);
+
+    assert_eq!(
+        product.set_pricing(3_500_000, Some(mint), UnitOfMeasure::Piece, 6),
+        Ok(())
+    );
+    assert_eq!(product.price_per_unit, 3_500_000);
+    assert_eq!(product.price_mint, Some(mint));
+    assert_eq!(product.unit, UnitOfMeasure::Piece);
+    assert_eq!(product.min_order_quantity, 6);
+}
+
+#[test]
+fn rejects_a_zero_price_or_minimum_order() {
+    let mut product = product();
+
+    assert_eq!(
+        product.set_pricing(0, None, UnitOfMeasure::Kilogram, 1),
+        Err(ErrorCode::InvalidPrice.into())
+    );
+    assert_eq!(
+        product.set_pricing(1_000, None, UnitOfMeasure::Kilogram, 0),
+        Err(ErrorCode::InvalidMinOrderQuantity.into())
+    );
+    assert_eq!(product.price_per_unit, 2_000_000);
+    assert_eq!(product.min_order_quantity, 1);
+}
+
+#[test]
+fn adjusts_stock_both_ways() {
+    let mut product = product();
+
+    assert_eq!(product.adjust_stock(20), Ok(()));
+    assert_eq!(product.available_stock, 500);
+    assert_eq!(product.adjust_stock(-500), Ok(()));
+    assert_eq!(product.available_stock, 0);
+}
+
+#[test]
+fn stock_cannot_go_negative() {
+    let mut product = product();
+
+    assert_eq!(
+        product.adjust_stock(-481),
+        Err(ErrorCode::InsufficientStock.into())
+    );
+    assert_eq!(product.available_stock, 480);
+}
+
+#[test]
+fn stock_cannot_overflow() {
+    let mut product = product();
+    product.available_stock = u64::MAX;
+
+    assert_eq!(
+        product.adjust_stock(1),
+        Err(ErrorCode::InsufficientStock.into())
+    );
+}
@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::MAX_RETURN_DATA;
+use farm_direct_blockchain::migration::{upgrade_farmer_profile, upgrade_product_cycle};
+use farm_direct_blockchain::{
+    history_genesis, new_product_id, DeliveryStatus, DeliveryUpdate, GrowthStage, GrowthUpdate, HistoryEntry,
+    ProvenanceProof, MAX_PROOF_DELIVERY_STEPS,
+};
+
+const FARMER_PROFILE_V1: &[u8] = include_bytes!("fixtures/farmer_profile_v1.bin");
+const PRODUCT_CYCLE_V1: &[u8] = include_bytes!("fixtures/product_cycle_v1.bin");
+
+fn fixture_farmer() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+fn growth(stage: GrowthStage, timestamp: i64) -> GrowthUpdate {
+    GrowthUpdate {
+        stage,
+        timestamp,
+        notes: String::new(),
+        firebase_image_urls: Vec::new(),
+        media: Vec::new(),
+    }
+}
+
+#[test]
+fn summarises_stage_changes_and_custody() {
+    let profile = upgrade_farmer_profile(FARMER_PROFILE_V1, &fixture_farmer()).unwrap();
+    let mut product = upgrade_product_cycle(PRODUCT_CYCLE_V1, &fixture_farmer()).unwrap();
+    product.growth_updates = vec![
+        growth(GrowthStage::Seeding, 100),
+        growth(GrowthStage::Growing, 200),
+        growth(GrowthStage::Growing, 300),
+        growth(GrowthStage::Harvest, 400),
+    ];
+
+    let proof = ProvenanceProof::new(Pubkey::new_unique(), &product, &profile);
+
+    assert_eq!(proof.farmer, fixture_farmer());
+    assert!(proof.farmer_verified);
+    assert_eq!(proof.certifications, vec!["BIO", "Lokalne"]);
+    let stages: Vec<_> = proof
+        .growth_timeline
+        .iter()
+        .map(|mark| (mark.stage, mark.reached_at))
+        .collect();
+    assert_eq!(
+        stages,
+        vec![
+            (GrowthStage::Seeding, 100),
+            (GrowthStage::Growing, 200),
+            (GrowthStage::Harvest, 400),
+        ]
+    );
+    assert_eq!(proof.delivery_chain.len(), 2);
+    assert_eq!(proof.delivery_chain[1].status, DeliveryStatus::InTransit);
+    assert_eq!(proof.delivery_chain[1].signed_by, fixture_farmer());
+}
+
+#[test]
+fn reports_the_history_chain_head() {
+    let profile = upgrade_farmer_profile(FARMER_PROFILE_V1, &fixture_farmer()).unwrap();
+    let mut product = upgrade_product_cycle(PRODUCT_CYCLE_V1, &fixture_farmer()).unwrap();
+
+    // The migration folds the stored entries in, growth updates first; later
+    // entries extend the chain as they are recorded
+    let mut entries: Vec<HistoryEntry> = product
+        .growth_updates
+        .iter()
+        .cloned()
+        .map(HistoryEntry::Growth)
+        .chain(product.delivery_updates.iter().cloned().map(HistoryEntry::Delivery))
+        .collect();
+    let harvest = HistoryEntry::ActualQuantity {
+        quantity: 480,
+        timestamp: 1_723_000_000,
+    };
+    product.history_hash = harvest.chain(&product.history_hash);
+    entries.push(harvest);
+
+    let fold = |entries: &[HistoryEntry]| {
+        entries
+            .iter()
+            .fold(history_genesis(&product.product_id), |prev, entry| entry.chain(&prev))
+    };
+    let proof = ProvenanceProof::new(Pubkey::default(), &product, &profile);
+    assert_eq!(proof.history_hash, fold(&entries));
+
+    // A verifier replaying an edited entry no longer reaches the reported head
+    if let HistoryEntry::Delivery(update) = &mut entries[product.growth_updates.len()] {
+        update.notes.push('!');
+    }
+    assert_ne!(proof.history_hash, fold(&entries));
+}
+
+#[test]
+fn recreated_product_starts_a_new_chain() {
+    // Same farmer and timestamp, so the same address; the farmer's product
+    // count has moved on in between
+    let address = Pubkey::find_program_address(
+        &[b"product", fixture_farmer().as_ref(), &1_723_000_000i64.to_le_bytes()],
+        &farm_direct_blockchain::ID,
+    )
+    .0;
+    let first = new_product_id(&address, 4);
+    let recreated = new_product_id(&address, 5);
+
+    assert_ne!(first, recreated);
+    assert_ne!(history_genesis(&first), history_genesis(&recreated));
+    assert_eq!(first, new_product_id(&address, 4));
+}
+
+#[test]
+fn fits_in_return_data() {
+    let mut profile = upgrade_farmer_profile(FARMER_PROFILE_V1, &fixture_farmer()).unwrap();
+    profile.certifications = vec!["x".repeat(120); 4];
+    let mut product = upgrade_product_cycle(PRODUCT_CYCLE_V1, &fixture_farmer()).unwrap();
+    product.growth_updates = (0..40)
+        .map(|i| growth(if i % 2 == 0 { GrowthStage::Growing } else { GrowthStage::Flowering }, i))
+        .collect();
+    product.delivery_updates = (0..20)
+        .map(|i| DeliveryUpdate {
+            status: DeliveryStatus::InTransit,
+            timestamp: i,
+            notes: String::new(),
+            location: None,
+            checkpoint: None,
+            signed_by: Pubkey::new_unique(),
+        })
+        .collect();
+
+    let proof = ProvenanceProof::new(Pubkey::new_unique(), &product, &profile);
+
+    assert_eq!(proof.delivery_chain.len(), MAX_PROOF_DELIVERY_STEPS);
+    assert_eq!(proof.delivery_chain.last().unwrap().timestamp, 19);
+    assert!(proof.try_to_vec().unwrap().len() <= MAX_RETURN_DATA);
+}
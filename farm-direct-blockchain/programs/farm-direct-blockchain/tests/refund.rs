@@ -0,0 +1,59 @@
+use farm_direct_blockchain::ErrorCode;
+
+mod common;
+
+use common::{backer, campaign};
+
+const AFTER_DEADLINE: i64 = 1_720_000_001;
+
+#[test]
+fn failed_campaign_refunds_every_contribution() {
+    let mut campaign = campaign();
+    campaign.contributors.push(campaign.contributors[0].clone());
+
+    assert_eq!(
+        campaign.refund_for(&backer(1), AFTER_DEADLINE, false),
+        Ok(2_000_000_000)
+    );
+}
+
+#[test]
+fn funded_or_running_campaign_is_not_refundable() {
+    let mut campaign = campaign();
+    assert_eq!(
+        campaign.refund_for(&backer(1), 1_715_000_000, false),
+        Err(ErrorCode::CampaignNotRefundable.into())
+    );
+
+    campaign.current_amount = campaign.goal_amount;
+    assert_eq!(
+        campaign.refund_for(&backer(1), AFTER_DEADLINE, false),
+        Err(ErrorCode::CampaignNotRefundable.into())
+    );
+
+    campaign.is_cancelled = true;
+    assert_eq!(
+        campaign.refund_for(&backer(1), 1_715_000_000, false),
+        Ok(1_000_000_000)
+    );
+}
+
+#[test]
+fn backers_holding_harvest_shares_are_not_refunded() {
+    let mut campaign = campaign();
+    campaign.current_amount = campaign.goal_amount;
+    campaign.is_cancelled = true;
+
+    assert_eq!(
+        campaign.refund_for(&backer(1), AFTER_DEADLINE, true),
+        Err(ErrorCode::SharesAlreadyClaimed.into())
+    );
+}
+
+#[test]
+fn strangers_get_nothing() {
+    assert_eq!(
+        campaign().refund_for(&backer(2), AFTER_DEADLINE, false),
+        Err(ErrorCode::NotAContributor.into())
+    );
+}
@@ -0,0 +1,193 @@
+use farm_direct_blockchain::{
+    is_valid_funding, CrowdfundingCampaign, ErrorCode, FundingOptions, StretchGoal,
+    MAX_STRETCH_GOALS,
+};
+
+mod common;
+
+use common::backer;
+
+const GOAL: u64 = 5_000_000_000;
+const NOW: i64 = 1_715_000_000;
+
+fn goal(amount: u64) -> StretchGoal {
+    StretchGoal {
+        amount,
+        milestone: "Drip irrigation".to_string(),
+    }
+}
+
+fn funding(hard_cap: Option<u64>, stretch_goals: Vec<StretchGoal>) -> FundingOptions {
+    FundingOptions {
+        allow_overfunding: true,
+        hard_cap,
+        stretch_goals,
+    }
+}
+
+fn campaign(stretch_goals: Vec<StretchGoal>) -> CrowdfundingCampaign {
+    let mut campaign = common::campaign();
+    campaign.goal_amount = GOAL;
+    campaign.allow_overfunding = true;
+    campaign.stretch_goals = stretch_goals;
+    campaign
+}
+
+#[test]
+fn stretch_goals_need_overfunding_and_rise_above_the_goal() {
+    assert!(is_valid_funding(
+        GOAL,
+        &FundingOptions {
+            allow_overfunding: false,
+            hard_cap: None,
+            stretch_goals: Vec::new(),
+        }
+    ));
+    assert!(is_valid_funding(
+        GOAL,
+        &funding(None, vec![goal(6_000_000_000)])
+    ));
+    assert!(is_valid_funding(
+        GOAL,
+        &funding(
+            Some(8_000_000_000),
+            vec![goal(6_000_000_000), goal(8_000_000_000)]
+        )
+    ));
+
+    let mut closed_at_goal = funding(None, vec![goal(6_000_000_000)]);
+    closed_at_goal.allow_overfunding = false;
+    assert!(!is_valid_funding(GOAL, &closed_at_goal));
+    assert!(!is_valid_funding(GOAL, &funding(None, vec![goal(GOAL)])));
+    assert!(!is_valid_funding(
+        GOAL,
+        &funding(Some(GOAL - 1), Vec::new())
+    ));
+    assert!(!is_valid_funding(
+        GOAL,
+        &funding(Some(7_000_000_000), vec![goal(8_000_000_000)])
+    ));
+    assert!(!is_valid_funding(
+        GOAL,
+        &funding(None, vec![goal(8_000_000_000), goal(6_000_000_000)])
+    ));
+    let too_many = (1..=MAX_STRETCH_GOALS as u64 + 1)
+        .map(|i| goal(GOAL + i))
+        .collect();
+    assert!(!is_valid_funding(GOAL, &funding(None, too_many)));
+}
+
+#[test]
+fn counts_reached_stretch_goals() {
+    let campaign = campaign(vec![goal(6_000_000_000), goal(8_000_000_000)]);
+
+    assert_eq!(campaign.stretch_goals_reached_by(GOAL), 0);
+    assert_eq!(campaign.stretch_goals_reached_by(6_000_000_000), 1);
+    assert_eq!(campaign.stretch_goals_reached_by(7_999_999_999), 1);
+    assert_eq!(campaign.stretch_goals_reached_by(9_000_000_000), 2);
+}
+
+#[test]
+fn closes_at_the_deadline_even_while_active() {
+    let mut campaign = campaign(Vec::new());
+    campaign.is_active = true;
+
+    assert!(campaign.is_open(campaign.deadline));
+    assert!(!campaign.is_open(campaign.deadline + 1));
+    campaign.is_active = false;
+    assert!(!campaign.is_open(campaign.deadline));
+}
+
+#[test]
+fn each_stretch_goal_is_announced_once() {
+    let mut campaign = campaign(vec![goal(6_000_000_000), goal(8_000_000_000)]);
+
+    // 1 SOL is in
+    assert_eq!(
+        campaign.record_contribution(backer(2), 5_500_000_000, NOW),
+        Ok(0..1)
+    );
+    assert_eq!(campaign.record_contribution(backer(2), 100, NOW), Ok(1..1));
+    assert_eq!(
+        campaign.record_contribution(backer(3), 1_500_000_000, NOW),
+        Ok(1..2)
+    );
+    assert_eq!(campaign.stretch_goals_reached, 2);
+    assert!(campaign
+        .record_contribution(backer(3), 1, NOW)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn one_contribution_can_reach_several_goals() {
+    let mut campaign = campaign(vec![goal(6_000_000_000), goal(8_000_000_000)]);
+
+    assert_eq!(
+        campaign.record_contribution(backer(2), 7_000_000_000, NOW),
+        Ok(0..2)
+    );
+}
+
+#[test]
+fn goals_reached_again_after_a_refund_are_not_announced_twice() {
+    let mut campaign = campaign(vec![goal(6_000_000_000)]);
+
+    assert_eq!(
+        campaign.record_contribution(backer(2), 5_000_000_000, NOW),
+        Ok(0..1)
+    );
+    // A refund drops the total back below the goal
+    campaign.current_amount -= 1_000_000_000;
+    assert!(campaign
+        .record_contribution(backer(3), 1_000_000_000, NOW)
+        .unwrap()
+        .is_empty());
+    assert_eq!(campaign.stretch_goals_reached, 1);
+}
+
+#[test]
+fn contribution_that_would_pass_the_cap_is_refused_whole() {
+    let mut campaign = campaign(Vec::new());
+    campaign.hard_cap = Some(6_000_000_000);
+
+    assert_eq!(
+        campaign.record_contribution(backer(2), 5_000_000_001, NOW),
+        Err(ErrorCode::ExceedsHardCap.into())
+    );
+    assert_eq!(campaign.current_amount, 1_000_000_000);
+    assert_eq!(campaign.contributors.len(), 1);
+    assert!(campaign.is_active);
+
+    // Landing exactly on the cap is accepted and closes the campaign
+    assert_eq!(
+        campaign.record_contribution(backer(2), 5_000_000_000, NOW),
+        Ok(0..0)
+    );
+    assert_eq!(campaign.current_amount, 6_000_000_000);
+    assert!(!campaign.is_active);
+}
+
+#[test]
+fn campaign_without_overfunding_closes_at_its_goal() {
+    let mut campaign = campaign(Vec::new());
+    campaign.allow_overfunding = false;
+
+    campaign
+        .record_contribution(backer(1), GOAL - 1_000_000_001, NOW)
+        .unwrap();
+    assert!(campaign.is_active);
+    campaign.record_contribution(backer(2), 1, NOW).unwrap();
+    assert!(!campaign.is_active);
+}
+
+#[test]
+fn repeat_backers_keep_one_entry() {
+    let mut campaign = campaign(Vec::new());
+
+    campaign.record_contribution(backer(1), 500, NOW).unwrap();
+
+    assert_eq!(campaign.contributors.len(), 1);
+    assert_eq!(campaign.contributors[0].amount, 1_000_000_500);
+    assert_eq!(campaign.contributors[0].timestamp, NOW);
+}
@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+use farm_direct_blockchain::{ErrorCode, Subscription};
+
+mod common;
+
+use common::{serialize, subscription};
+
+const RENT: u64 = 890_880;
+
+#[test]
+fn instalment_needs_a_confirmed_delivery() {
+    let mut subscription = subscription(4);
+
+    assert!(!subscription.release_instalment());
+    assert_eq!(subscription.delivered_count, 0);
+
+    subscription.confirm_delivery();
+    assert!(subscription.release_instalment());
+    assert_eq!(subscription.delivered_count, 1);
+    assert!(subscription.is_active);
+}
+
+#[test]
+fn confirmed_delivery_is_paid_once() {
+    let mut subscription = subscription(4);
+
+    // Confirming the same delivery twice still opens a single instalment
+    subscription.confirm_delivery();
+    subscription.confirm_delivery();
+    assert!(subscription.release_instalment());
+    assert!(!subscription.release_instalment());
+    assert_eq!(subscription.delivered_count, 1);
+
+    subscription.confirm_delivery();
+    assert!(subscription.release_instalment());
+    assert_eq!(subscription.delivered_count, 2);
+}
+
+#[test]
+fn last_instalment_ends_the_subscription() {
+    let mut subscription = subscription(2);
+    for _ in 0..2 {
+        subscription.confirm_delivery();
+        assert!(subscription.release_instalment());
+    }
+    assert!(!subscription.is_active);
+
+    subscription.confirm_delivery();
+    assert!(!subscription.release_instalment());
+    assert_eq!(subscription.delivered_count, 2);
+}
+
+#[test]
+fn cancelling_refunds_only_undelivered_instalments() {
+    let mut subscription = subscription(4);
+    subscription.confirm_delivery();
+    subscription.release_instalment();
+
+    assert_eq!(
+        subscription.cancellation_refund(RENT),
+        Ok(3 * 50_000_000 + RENT)
+    );
+}
+
+#[test]
+fn confirmed_delivery_blocks_cancelling() {
+    let mut subscription = subscription(4);
+    subscription.confirm_delivery();
+
+    // Otherwise the buyer would take back a box they already confirmed
+    assert_eq!(
+        subscription.cancellation_refund(RENT),
+        Err(ErrorCode::InstalmentAwaitingRelease.into())
+    );
+
+    subscription.release_instalment();
+    assert_eq!(
+        subscription.cancellation_refund(RENT),
+        Ok(3 * 50_000_000 + RENT)
+    );
+}
+
+#[test]
+fn finished_subscription_refunds_the_rent_reserve() {
+    let mut subscription = subscription(1);
+    subscription.confirm_delivery();
+    subscription.release_instalment();
+
+    assert_eq!(subscription.cancellation_refund(RENT), Ok(RENT));
+}
+
+#[test]
+fn vault_is_derived_from_the_subscription() {
+    let key = Pubkey::new_unique();
+    let (vault, bump) = Pubkey::find_program_address(
+        &[b"subscription_vault", key.as_ref()],
+        &farm_direct_blockchain::ID,
+    );
+    let mut subscription = subscription(4);
+    subscription.vault_bump = bump;
+
+    assert_eq!(subscription.vault_address(&key), Some(vault));
+    assert_ne!(subscription.vault_address(&Pubkey::new_unique()), Some(vault));
+}
+
+#[test]
+fn fits_in_space() {
+    let data = serialize(&subscription(4), Subscription::SPACE);
+    let reloaded = Subscription::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(reloaded.total_deliveries, 4);
+}
@@ -1,8 +1,10 @@
 // programs/farm-direct/src/lib.rs
+// Generowane przez Anchora handlery IDL wołają przestarzałe `AccountInfo::realloc`
+#![allow(deprecated)]
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::hash::hashv;
 
-declare_id!("FarmDirect11111111111111111111111111111111");
+declare_id!("FarmDirect1111111111111111111111111111111111");
 
 #[program]
 pub mod farm_direct {
@@ -33,6 +35,55 @@ pub mod farm_direct {
         Ok(())
     }
 
+    // Przeniesienie profilu z adresu keypair na PDA [b"farmer_profile", farmer]
+    pub fn migrate_farmer_profile(ctx: Context<MigrateFarmerProfile>) -> Result<()> {
+        let legacy_profile = &ctx.accounts.legacy_profile;
+        let farmer_profile = &mut ctx.accounts.farmer_profile;
+        let clock = Clock::get()?;
+
+        farmer_profile.farmer_wallet = legacy_profile.farmer_wallet;
+        farmer_profile.encrypted_data = legacy_profile.encrypted_data.clone();
+        farmer_profile.public_name = legacy_profile.public_name.clone();
+        farmer_profile.region = legacy_profile.region.clone();
+        farmer_profile.certifications = legacy_profile.certifications.clone();
+        farmer_profile.verification_status = legacy_profile.verification_status;
+        farmer_profile.reputation_score = legacy_profile.reputation_score;
+        farmer_profile.total_products = legacy_profile.total_products;
+        farmer_profile.created_at = legacy_profile.created_at;
+        farmer_profile.updated_at = clock.unix_timestamp;
+
+        // Stare konto zamykane jest przez `close = farmer` w kontekście
+        Ok(())
+    }
+
+    // Portfel mógł utworzyć kilka profili pod adresami keypair, a przenieść da się
+    // tylko jeden. Pozostałe dolicza się do profilu PDA i zamyka.
+    pub fn merge_legacy_profile(ctx: Context<MergeLegacyProfile>) -> Result<()> {
+        let legacy_profile = &ctx.accounts.legacy_profile;
+        let farmer_profile = &mut ctx.accounts.farmer_profile;
+        let clock = Clock::get()?;
+
+        farmer_profile.total_products = farmer_profile
+            .total_products
+            .checked_add(legacy_profile.total_products)
+            .ok_or(ErrorCode::InvalidAmount)?;
+        farmer_profile.created_at = farmer_profile.created_at.min(legacy_profile.created_at);
+        // Zachowujemy lepszą reputację i weryfikację oraz wszystkie certyfikaty
+        farmer_profile.reputation_score = farmer_profile
+            .reputation_score
+            .max(legacy_profile.reputation_score);
+        farmer_profile.verification_status |= legacy_profile.verification_status;
+        for certification in &legacy_profile.certifications {
+            if !farmer_profile.certifications.contains(certification) {
+                farmer_profile.certifications.push(certification.clone());
+            }
+        }
+        farmer_profile.updated_at = clock.unix_timestamp;
+
+        // Stare konto zamykane jest przez `close = farmer` w kontekście
+        Ok(())
+    }
+
     // Aktualizacja profilu rolnika
     pub fn update_farmer_profile(
         ctx: Context<UpdateFarmerProfile>,
@@ -76,7 +127,7 @@ pub mod farm_direct {
         let farmer_profile = &mut ctx.accounts.farmer_profile;
         let clock = Clock::get()?;
 
-        product_cycle.product_id = hash(&[
+        product_cycle.product_id = hashv(&[
             ctx.accounts.farmer.key().as_ref(),
             &clock.unix_timestamp.to_le_bytes(),
         ]).to_string();
@@ -174,7 +225,7 @@ pub mod farm_direct {
         let campaign = &mut ctx.accounts.campaign;
         let clock = Clock::get()?;
 
-        campaign.campaign_id = hash(&[
+        campaign.campaign_id = hashv(&[
             ctx.accounts.farmer.key().as_ref(),
             &clock.unix_timestamp.to_le_bytes(),
         ]).to_string();
@@ -234,6 +285,15 @@ pub mod farm_direct {
     }
 }
 
+// Autoryzacja: profil i produkty może zmieniać tylko portfel, który je utworzył
+pub fn is_profile_owner(profile: &FarmerProfile, signer: &Pubkey) -> bool {
+    profile.farmer_wallet == *signer
+}
+
+pub fn is_product_owner(product: &ProductCycle, signer: &Pubkey) -> bool {
+    product.farmer == *signer
+}
+
 // Struktury danych
 #[account]
 pub struct FarmerProfile {
@@ -338,18 +398,73 @@ pub enum CampaignType {
 }
 
 // Konteksty
+// Jeden profil na portfel: adres wynika z klucza rolnika
 #[derive(Accounts)]
 pub struct InitializeFarmer<'info> {
-    #[account(init, payer = farmer, space = 8 + 1000)]
+    #[account(
+        init,
+        payer = farmer,
+        space = 8 + 1000,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
     pub farmer_profile: Account<'info, FarmerProfile>,
     #[account(mut)]
     pub farmer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateFarmerProfile<'info> {
+    // Profil utworzony pod dowolnym adresem keypair przed wprowadzeniem PDA
+    #[account(
+        mut,
+        close = farmer,
+        constraint = is_profile_owner(&legacy_profile, &farmer.key()) @ ErrorCode::UnauthorizedFarmer
+    )]
+    pub legacy_profile: Account<'info, FarmerProfile>,
+    #[account(
+        init,
+        payer = farmer,
+        space = 8 + 1000,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MergeLegacyProfile<'info> {
+    // Kolejny profil keypair tego samego portfela; nie może to być sam profil PDA
+    #[account(
+        mut,
+        close = farmer,
+        constraint = is_profile_owner(&legacy_profile, &farmer.key()) @ ErrorCode::UnauthorizedFarmer,
+        constraint = legacy_profile.key() != farmer_profile.key() @ ErrorCode::UnauthorizedFarmer
+    )]
+    pub legacy_profile: Account<'info, FarmerProfile>,
+    #[account(
+        mut,
+        constraint = is_profile_owner(&farmer_profile, &farmer.key()) @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
+    pub farmer_profile: Account<'info, FarmerProfile>,
+    #[account(mut)]
+    pub farmer: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateFarmerProfile<'info> {
-    #[account(mut, has_one = farmer_wallet @ ErrorCode::UnauthorizedFarmer)]
+    #[account(
+        mut,
+        constraint = is_profile_owner(&farmer_profile, &farmer.key()) @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
     pub farmer_profile: Account<'info, FarmerProfile>,
     #[account(mut)]
     pub farmer: Signer<'info>,
@@ -359,7 +474,12 @@ pub struct UpdateFarmerProfile<'info> {
 pub struct CreateProduct<'info> {
     #[account(init, payer = farmer, space = 8 + 2000)]
     pub product_cycle: Account<'info, ProductCycle>,
-    #[account(mut, has_one = farmer_wallet @ ErrorCode::UnauthorizedFarmer)]
+    #[account(
+        mut,
+        constraint = is_profile_owner(&farmer_profile, &farmer.key()) @ ErrorCode::UnauthorizedFarmer,
+        seeds = [b"farmer_profile", farmer.key().as_ref()],
+        bump
+    )]
     pub farmer_profile: Account<'info, FarmerProfile>,
     #[account(mut)]
     pub farmer: Signer<'info>,
@@ -368,7 +488,10 @@ pub struct CreateProduct<'info> {
 
 #[derive(Accounts)]
 pub struct UpdateProduct<'info> {
-    #[account(mut, has_one = farmer @ ErrorCode::UnauthorizedFarmer)]
+    #[account(
+        mut,
+        constraint = is_product_owner(&product_cycle, &farmer.key()) @ ErrorCode::UnauthorizedFarmer
+    )]
     pub product_cycle: Account<'info, ProductCycle>,
     #[account(mut)]
     pub farmer: Signer<'info>,
@@ -0,0 +1,87 @@
+use std::collections::BTreeSet;
+
+use anchor_lang::prelude::*;
+use farm_direct::{
+    is_product_owner, is_profile_owner, ErrorCode, FarmerProfile, ProductCycle,
+    UpdateFarmerProfile, UpdateFarmerProfileBumps, UpdateProduct, UpdateProductBumps,
+};
+
+mod common;
+
+use common::{farmer, product, profile, profile_address, stranger, TestAccount};
+
+fn update_profile(
+    profile_address: Pubkey,
+    profile: &FarmerProfile,
+    signer: Pubkey,
+    signed: bool,
+) -> Result<()> {
+    let mut profile = TestAccount::program(profile_address, profile);
+    let mut signer = TestAccount::wallet(signer);
+    let infos = [profile.info(false), signer.info(signed)];
+
+    UpdateFarmerProfile::try_accounts(
+        &farm_direct::ID,
+        &mut &infos[..],
+        &[],
+        &mut UpdateFarmerProfileBumps::default(),
+        &mut BTreeSet::new(),
+    )
+    .map(|_| ())
+}
+
+fn update_product(product: &ProductCycle, signer: Pubkey) -> Result<()> {
+    let mut product = TestAccount::program(Pubkey::new_from_array([3; 32]), product);
+    let mut signer = TestAccount::wallet(signer);
+    let infos = [product.info(false), signer.info(true)];
+
+    UpdateProduct::try_accounts(
+        &farm_direct::ID,
+        &mut &infos[..],
+        &[],
+        &mut UpdateProductBumps::default(),
+        &mut BTreeSet::new(),
+    )
+    .map(|_| ())
+}
+
+#[test]
+fn owner_may_modify_profile() {
+    assert!(is_profile_owner(&profile(), &farmer()));
+    assert_eq!(
+        update_profile(profile_address(&farmer()), &profile(), farmer(), true),
+        Ok(())
+    );
+}
+
+#[test]
+fn stranger_cannot_modify_profile() {
+    assert!(!is_profile_owner(&profile(), &stranger()));
+    // Pointing the stranger's own profile address at the farmer's data
+    assert_eq!(
+        update_profile(profile_address(&stranger()), &profile(), stranger(), true),
+        Err(ErrorCode::UnauthorizedFarmer.into())
+    );
+    assert!(update_profile(profile_address(&farmer()), &profile(), stranger(), true).is_err());
+}
+
+#[test]
+fn profile_owner_has_to_sign() {
+    assert_eq!(
+        update_profile(profile_address(&farmer()), &profile(), farmer(), false),
+        Err(anchor_lang::error::ErrorCode::AccountNotSigner.into())
+    );
+}
+
+#[test]
+fn stranger_cannot_modify_product() {
+    let product = product();
+
+    assert!(is_product_owner(&product, &farmer()));
+    assert!(!is_product_owner(&product, &stranger()));
+    assert_eq!(update_product(&product, farmer()), Ok(()));
+    assert_eq!(
+        update_product(&product, stranger()),
+        Err(ErrorCode::UnauthorizedFarmer.into())
+    );
+}
@@ -0,0 +1,196 @@
+// Accounts and the bits of the runtime the contexts touch, so they can be run
+// off-chain.
+#![allow(dead_code)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::system_program;
+use farm_direct::{FarmerProfile, ProductCycle};
+
+pub const NOW: i64 = 1_700_000_000;
+
+// SystemError::AccountAlreadyInUse
+pub const ACCOUNT_ALREADY_IN_USE: u32 = 0;
+
+pub fn farmer() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+pub fn stranger() -> Pubkey {
+    Pubkey::new_from_array([9; 32])
+}
+
+pub fn profile_address(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"farmer_profile", wallet.as_ref()], &farm_direct::ID).0
+}
+
+pub fn profile() -> FarmerProfile {
+    FarmerProfile {
+        farmer_wallet: farmer(),
+        encrypted_data: String::new(),
+        public_name: "Gospodarstwo Kowalskich".to_string(),
+        region: "mazowieckie".to_string(),
+        certifications: vec![],
+        verification_status: false,
+        reputation_score: 0,
+        total_products: 1,
+        created_at: 1_600_000_000,
+        updated_at: 1_600_000_000,
+    }
+}
+
+pub fn product() -> ProductCycle {
+    ProductCycle {
+        product_id: "jablka-2024".to_string(),
+        farmer: farmer(),
+        product_name: "Jabłka".to_string(),
+        category: "owoce".to_string(),
+        description: String::new(),
+        estimated_harvest_date: 1_700_000_000,
+        estimated_quantity: 500,
+        actual_quantity: 0,
+        firebase_image_urls: vec![],
+        growth_updates: vec![],
+        delivery_updates: vec![],
+        created_at: 1_600_000_000,
+        updated_at: 1_600_000_000,
+    }
+}
+
+// Backing storage for an `AccountInfo`, as the runtime would pass it in
+pub struct TestAccount {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    executable: bool,
+}
+
+impl TestAccount {
+    pub fn program<T: AccountSerialize>(key: Pubkey, account: &T) -> Self {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        Self {
+            key,
+            owner: farm_direct::ID,
+            lamports: Rent::default().minimum_balance(8 + 1000),
+            data,
+            executable: false,
+        }
+    }
+
+    pub fn wallet(key: Pubkey) -> Self {
+        Self {
+            key,
+            owner: system_program::ID,
+            lamports: 10_000_000_000,
+            data: Vec::new(),
+            executable: false,
+        }
+    }
+
+    // An address nothing has been created at yet
+    pub fn empty(key: Pubkey) -> Self {
+        Self {
+            lamports: 0,
+            ..Self::wallet(key)
+        }
+    }
+
+    pub fn system_program() -> Self {
+        Self {
+            key: system_program::ID,
+            owner: Pubkey::default(),
+            lamports: 1,
+            data: Vec::new(),
+            executable: true,
+        }
+    }
+
+    pub fn info(&mut self, is_signer: bool) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            is_signer,
+            !self.executable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+}
+
+// Serves the clock and rent sysvars, and carries out the system program
+// instructions Anchor's `init` issues.
+struct Runtime;
+
+impl SyscallStubs for Runtime {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: NOW,
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> std::result::Result<(), ProgramError> {
+        assert_eq!(instruction.program_id, system_program::ID);
+        let account = |index: usize| {
+            let key = instruction.accounts[index].pubkey;
+            account_infos.iter().find(|info| *info.key == key).unwrap()
+        };
+        let data = &instruction.data;
+        let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+        let key_at = |at: usize| Pubkey::new_from_array(data[at..at + 32].try_into().unwrap());
+
+        match u32::from_le_bytes(data[..4].try_into().unwrap()) {
+            // CreateAccount { lamports, space, owner }
+            0 => {
+                if account(1).lamports() > 0 {
+                    return Err(ProgramError::Custom(ACCOUNT_ALREADY_IN_USE));
+                }
+                transfer(account(0), account(1), u64_at(4));
+                allocate(account(1), u64_at(12))?;
+                account(1).assign(&key_at(20));
+            }
+            // Assign { owner }
+            1 => account(0).assign(&key_at(4)),
+            // Transfer { lamports }
+            2 => transfer(account(0), account(1), u64_at(4)),
+            // Allocate { space }
+            8 => allocate(account(0), u64_at(4))?,
+            other => panic!("system instruction {other} is not emulated"),
+        }
+        Ok(())
+    }
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) {
+    **from.lamports.borrow_mut() -= lamports;
+    **to.lamports.borrow_mut() += lamports;
+}
+
+fn allocate(account: &AccountInfo, space: u64) -> std::result::Result<(), ProgramError> {
+    if !account.data_is_empty() || *account.owner != system_program::ID {
+        return Err(ProgramError::Custom(ACCOUNT_ALREADY_IN_USE));
+    }
+    *account.data.borrow_mut() = Box::leak(vec![0; space as usize].into_boxed_slice());
+    Ok(())
+}
+
+pub fn runtime() {
+    set_syscall_stubs(Box::new(Runtime));
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeSet;
+
+use anchor_lang::prelude::*;
+use farm_direct::{
+    ErrorCode, FarmerProfile, InitializeFarmer, InitializeFarmerBumps, MergeLegacyProfile,
+    MergeLegacyProfileBumps, MigrateFarmerProfile, MigrateFarmerProfileBumps,
+};
+
+mod common;
+
+use common::{
+    farmer, profile, profile_address, runtime, stranger, TestAccount, ACCOUNT_ALREADY_IN_USE, NOW,
+};
+
+fn initialize<'info>(infos: &'info [AccountInfo<'info>]) -> Result<()> {
+    InitializeFarmer::try_accounts(
+        &farm_direct::ID,
+        &mut &infos[..],
+        &[],
+        &mut InitializeFarmerBumps::default(),
+        &mut BTreeSet::new(),
+    )
+    .map(|_| ())
+}
+
+// Runs the migration of `legacy` signed by `signer` and returns the new profile
+fn migrate(legacy: &FarmerProfile, signer: Pubkey) -> Result<FarmerProfile> {
+    runtime();
+    let mut legacy = TestAccount::program(Pubkey::new_unique(), legacy);
+    let mut pda = TestAccount::empty(profile_address(&signer));
+    let mut signer = TestAccount::wallet(signer);
+    let mut system = TestAccount::system_program();
+    let infos = [
+        legacy.info(false),
+        pda.info(false),
+        signer.info(true),
+        system.info(false),
+    ];
+
+    let mut bumps = MigrateFarmerProfileBumps::default();
+    let mut accounts = MigrateFarmerProfile::try_accounts(
+        &farm_direct::ID,
+        &mut &infos[..],
+        &[],
+        &mut bumps,
+        &mut BTreeSet::new(),
+    )?;
+    farm_direct::farm_direct::migrate_farmer_profile(Context::new(
+        &farm_direct::ID,
+        &mut accounts,
+        &[],
+        bumps,
+    ))?;
+
+    Ok(accounts.farmer_profile.clone().into_inner())
+}
+
+fn merge(legacy_address: Pubkey, legacy: &FarmerProfile, signer: Pubkey) -> Result<FarmerProfile> {
+    runtime();
+    let mut migrated = profile();
+    migrated.total_products = 3;
+    migrated.certifications = vec!["BIO".to_string()];
+    migrated.reputation_score = 20;
+    let mut pda = TestAccount::program(profile_address(&farmer()), &migrated);
+    let mut legacy = if legacy_address == profile_address(&farmer()) {
+        TestAccount::program(legacy_address, &migrated)
+    } else {
+        TestAccount::program(legacy_address, legacy)
+    };
+    let mut signer = TestAccount::wallet(signer);
+    let infos = [legacy.info(false), pda.info(false), signer.info(true)];
+
+    let mut bumps = MergeLegacyProfileBumps::default();
+    let mut accounts = MergeLegacyProfile::try_accounts(
+        &farm_direct::ID,
+        &mut &infos[..],
+        &[],
+        &mut bumps,
+        &mut BTreeSet::new(),
+    )?;
+    farm_direct::farm_direct::merge_legacy_profile(Context::new(
+        &farm_direct::ID,
+        &mut accounts,
+        &[],
+        bumps,
+    ))?;
+
+    Ok(accounts.farmer_profile.clone().into_inner())
+}
+
+#[test]
+fn migrates_a_keypair_profile_to_the_wallet_address() {
+    let mut legacy = profile();
+    legacy.verification_status = true;
+    legacy.reputation_score = 12;
+
+    let migrated = migrate(&legacy, farmer()).unwrap();
+
+    assert_eq!(migrated.farmer_wallet, farmer());
+    assert_eq!(migrated.public_name, legacy.public_name);
+    assert!(migrated.verification_status);
+    assert_eq!(migrated.reputation_score, 12);
+    assert_eq!(migrated.total_products, legacy.total_products);
+    assert_eq!(migrated.created_at, legacy.created_at);
+    assert_eq!(migrated.updated_at, NOW);
+}
+
+#[test]
+fn stranger_cannot_migrate_someone_elses_profile() {
+    assert_eq!(
+        migrate(&profile(), stranger()).err(),
+        Some(ErrorCode::UnauthorizedFarmer.into())
+    );
+}
+
+#[test]
+fn wallet_initializes_one_profile_only() {
+    runtime();
+    let mut pda = TestAccount::empty(profile_address(&farmer()));
+    let mut wallet = TestAccount::wallet(farmer());
+    let mut system = TestAccount::system_program();
+    let infos = [pda.info(false), wallet.info(true), system.info(false)];
+
+    assert_eq!(initialize(&infos), Ok(()));
+    // The profile address follows from the wallet, so the second one collides
+    assert_eq!(
+        initialize(&infos),
+        Err(ProgramError::Custom(ACCOUNT_ALREADY_IN_USE).into())
+    );
+}
+
+#[test]
+fn profile_cannot_be_initialized_off_the_wallet_address() {
+    runtime();
+    let mut elsewhere = TestAccount::empty(Pubkey::new_unique());
+    let mut wallet = TestAccount::wallet(farmer());
+    let mut system = TestAccount::system_program();
+    let infos = [elsewhere.info(false), wallet.info(true), system.info(false)];
+
+    assert_eq!(
+        initialize(&infos),
+        Err(anchor_lang::error::ErrorCode::ConstraintSeeds.into())
+    );
+}
+
+#[test]
+fn further_keypair_profiles_merge_into_the_migrated_one() {
+    let mut second = profile();
+    second.total_products = 2;
+    second.created_at = 1_500_000_000;
+
+    let merged = merge(Pubkey::new_unique(), &second, farmer()).unwrap();
+
+    assert_eq!(merged.total_products, 5);
+    assert_eq!(merged.created_at, 1_500_000_000);
+    assert_eq!(merged.updated_at, NOW);
+}
+
+#[test]
+fn merge_keeps_the_best_standing_and_every_certification() {
+    let mut second = profile();
+    second.certifications = vec!["Lokalne".to_string(), "BIO".to_string()];
+    second.verification_status = true;
+    second.reputation_score = 12;
+
+    let merged = merge(Pubkey::new_unique(), &second, farmer()).unwrap();
+
+    assert_eq!(merged.certifications, vec!["BIO", "Lokalne"]);
+    assert!(merged.verification_status);
+    assert_eq!(merged.reputation_score, 20);
+
+    second.reputation_score = 30;
+    let merged = merge(Pubkey::new_unique(), &second, farmer()).unwrap();
+    assert_eq!(merged.reputation_score, 30);
+}
+
+#[test]
+fn migrated_profile_cannot_be_merged_into_itself() {
+    assert_eq!(
+        merge(profile_address(&farmer()), &profile(), farmer()).err(),
+        Some(ErrorCode::UnauthorizedFarmer.into())
+    );
+}
+
+#[test]
+fn stranger_cannot_merge_someone_elses_profile() {
+    assert_eq!(
+        merge(Pubkey::new_unique(), &profile(), stranger()).err(),
+        Some(ErrorCode::UnauthorizedFarmer.into())
+    );
+}
@@ -15,6 +15,19 @@ import { Alert, AlertDescription } from '@/components/ui/alert';
 import { useToast } from '@/components/ui/use-toast';
 import { Loader2 } from 'lucide-react';
 
+const categories = [
+  "Fruits",
+  "Vegetables",
+  "Dairy",
+  "Meat",
+  "Eggs",
+  "Honey",
+  "Grains",
+  "Herbs",
+  "Preserves",
+  "Other"
+];
+
 const productSchema = z.object({
   name: z.string().min(2, 'Product name is required'),
   description: z.string().min(10, 'Description must be at least 10 characters'),
@@ -137,7 +150,16 @@ const ProductForm = () => {
           <div className="grid grid-cols-2 gap-4">
             <div className="space-y-2">
               <Label htmlFor="category">Category</Label>
-              <Input id="category" placeholder="Vegetables, Fruits, Dairy, etc." {...register('category')} />
+              <select
+                id="category"
+                className="w-full border border-gray-300 rounded-md px-3 py-2"
+                {...register('category')}
+              >
+                <option value="">Select Category</option>
+                {categories.map(category => (
+                  <option key={category} value={category}>{category}</option>
+                ))}
+              </select>
               {errors.category && (
                 <p className="text-sm text-red-500">{errors.category.message}</p>
               )}
@@ -24,7 +24,6 @@ const categories = [
   "Eggs", 
   "Honey", 
   "Grains", 
-  "Baked Goods", 
   "Herbs",
   "Preserves",
   "Other"
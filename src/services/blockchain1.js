@@ -189,10 +189,26 @@ class BlockchainService {
 
       const farmerProfilePDA = new PublicKey(userProfile.blockchainProfilePDA);
 
+      // Mapowanie kategorii na enum ProductCategory
+      const categoryMapping = {
+        'Vegetables': { vegetables: {} },
+        'Fruits': { fruits: {} },
+        'Dairy': { dairy: {} },
+        'Meat': { meat: {} },
+        'Eggs': { eggs: {} },
+        'Grains': { grains: {} },
+        'Honey': { honey: {} },
+        'Herbs': { herbs: {} },
+        'Preserves': { preserves: {} },
+      };
+
+      const category = categoryMapping[productData.category] || { other: {} };
+
       const tx = await this.program.methods
         .createProduct(
           productData.name,
-          productData.category,
+          category,
+          null,
           productData.description,
           Math.floor(new Date(productData.estimatedHarvestDate || Date.now() + 90 * 24 * 60 * 60 * 1000).getTime() / 1000),
           productData.stockQuantity || 0,